@@ -1,11 +1,14 @@
 use machine::blocks::BlockData;
 use machine::canvas::wire::{Port, Wire};
-pub use machine::canvas::{Canvas, CanvasError};
+pub use machine::canvas::{Canvas, CanvasError, MailboxOrder, OverflowPolicy};
+use machine::mem::LayoutPreset;
+use machine::sequencer::Schedule;
 use machine::status::MachineStatus;
 use machine::Register::{FP, PC, SP};
-use machine::{Action, Event, Message};
+use machine::{Action, ControlFlowGraph, Event, Message, Parser};
 use serde::{Deserialize, Serialize};
 use serde_wasm_bindgen::to_value;
+use crate::host::browser_host;
 use wasm_bindgen::prelude::*;
 
 const NULL: JsValue = JsValue::NULL;
@@ -32,6 +35,9 @@ pub struct InspectedMachine {
     pub inbox_size: usize,
     pub outbox_size: usize,
     pub status: MachineStatus,
+
+    /// How many instructions has the machine executed?
+    pub cycles: u16,
 }
 
 type Return = Result<JsValue, JsValue>;
@@ -43,6 +49,13 @@ fn returns<T: Serialize>(value: Result<T, CanvasError>) -> Return {
     }
 }
 
+/// Canvas with the host functions of the browser.
+fn new_canvas() -> Canvas {
+    let mut canvas = Canvas::new();
+    canvas.set_host(browser_host());
+    canvas
+}
+
 fn return_raw<T: Serialize>(value: Result<T, CanvasError>) -> Result<T, JsValue> {
     match value {
         Ok(v) => Ok(v),
@@ -55,7 +68,7 @@ fn return_raw<T: Serialize>(value: Result<T, CanvasError>) -> Result<T, JsValue>
 impl Controller {
     pub fn create() -> Controller {
         Controller {
-            canvas: Canvas::new(),
+            canvas: new_canvas(),
         }
     }
 
@@ -94,6 +107,10 @@ impl Controller {
         returns(self.canvas.add_machine_with_id(id))
     }
 
+    pub fn add_machine_with_layout(&mut self, preset: LayoutPreset) -> Result<u16, JsValue> {
+        return_raw(self.canvas.add_machine_with_layout(preset.into()))
+    }
+
     pub fn remove_block(&mut self, id: u16) -> Return {
         returns(self.canvas.remove_block(id))
     }
@@ -151,6 +168,7 @@ impl Controller {
             inbox_size: m.inbox.len(),
             outbox_size: m.outbox.len(),
             status,
+            cycles: m.cycles,
         };
 
         Ok(to_value(&state)?)
@@ -180,6 +198,16 @@ impl Controller {
         Ok(to_value(&m.mem.read_stack(size))?)
     }
 
+    /// Build the control-flow graph and the call graph of the source, to render beside the editor.
+    pub fn get_control_flow_graph(&self, source: &str) -> Return {
+        let mut parser = Parser::new(source);
+
+        match parser.parse() {
+            Ok(()) => Ok(to_value(&ControlFlowGraph::new(&parser))?),
+            Err(error) => Err(to_value(&error)?),
+        }
+    }
+
     /// Allows the frontend to consume events from the machine.
     pub fn consume_machine_side_effects(&mut self, id: u16) -> Return {
         Ok(to_value(&self.canvas.seq.consume_side_effects(id))?)
@@ -194,6 +222,20 @@ impl Controller {
         self.canvas.machine_cycle_per_tick = cycle_per_tick;
     }
 
+    /// Set the clock speed and priority of a single machine.
+    pub fn set_machine_schedule(&mut self, id: u16, schedule: Schedule) -> Return {
+        returns(self.canvas.set_machine_schedule(id, schedule))
+    }
+
+    pub fn get_machine_schedule(&self, id: u16) -> Return {
+        Ok(to_value(&self.canvas.seq.get_schedule(id))?)
+    }
+
+    /// Supply text to the machine waiting on `read_char` or `read_line`.
+    pub fn provide_input(&mut self, id: u16, text: &str) -> Return {
+        returns(self.canvas.provide_input(id, text))
+    }
+
     pub fn send_message(&mut self, message: Message) -> Return {
         returns(self.canvas.send_message_to_port(message))
     }
@@ -202,6 +244,26 @@ impl Controller {
         returns(self.canvas.send_message_to_block(block_id, action))
     }
 
+    /// Set the order in which the block or machine takes its incoming messages.
+    pub fn set_mailbox_order(&mut self, id: u16, order: MailboxOrder) -> Return {
+        returns(self.canvas.set_mailbox_order(id, order))
+    }
+
+    /// Set what happens to messages that arrive when the inbox of the block or machine is full.
+    pub fn set_overflow_policy(&mut self, id: u16, policy: OverflowPolicy) -> Return {
+        returns(self.canvas.set_overflow_policy(id, policy))
+    }
+
+    /// How many messages were dropped, as the inbox of the block or machine is full?
+    pub fn get_dropped_messages(&self, id: u16) -> Result<u32, JsValue> {
+        return_raw(self.canvas.mailbox(id).map(|mailbox| mailbox.dropped))
+    }
+
+    /// How many messages were dropped on the wire, as the recipient's inbox is full?
+    pub fn get_wire_dropped_messages(&self, wire_id: u16) -> u32 {
+        self.canvas.wire_dropped.get(&wire_id).copied().unwrap_or(0)
+    }
+
     pub fn update_block(&mut self, id: u16, data: BlockData) -> Return {
         returns(self.canvas.update_block(id, data))
     }
@@ -214,12 +276,17 @@ impl Controller {
         returns(self.canvas.reset_block(id))
     }
 
+    /// Seed the random number generators, so generative patches replay identically.
+    pub fn set_seed(&mut self, seed: u32) {
+        self.canvas.set_seed(seed);
+    }
+
     pub fn set_await_watchdog(&mut self, state: bool) {
         self.canvas.seq.await_watchdog = state;
     }
 
     pub fn clear(&mut self) {
-        self.canvas = Canvas::new();
+        self.canvas = new_canvas();
     }
 
     /// Serialize the entire canvas state - very slow!
@@ -234,7 +301,7 @@ impl Controller {
 
         for m in canvas.seq.machines.iter_mut() {
             m.inbox.clear();
-            m.mem.buffer = vec![];
+            m.mem.buffer = Default::default();
             m.reg.buffer = vec![];
         }
 
@@ -273,6 +340,16 @@ impl Controller {
         Ok(true.into())
     }
 
+    /// Allow the machine to write to its own code segment.
+    pub fn set_self_modifying(&mut self, id: u16, state: bool) -> Return {
+        let Some(m) = self.canvas.seq.get_mut(id) else {
+            return Ok(false.into());
+        };
+
+        m.mem.self_modifying = state;
+        Ok(true.into())
+    }
+
     pub fn wake(&mut self, machine_id: u16) {
         self.canvas.seq.wake(machine_id);
     }
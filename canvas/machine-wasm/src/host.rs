@@ -0,0 +1,23 @@
+use js_sys::Date;
+use machine::cli::host::{SYSCALL_UNIX_TIME, SYSCALL_UPTIME_MS};
+use machine::HostRegistry;
+
+/// Host functions available to programs running in the browser.
+/// They share the syscall numbers of the command line, so programs behave the same in both.
+pub fn browser_host() -> HostRegistry {
+    let mut host = HostRegistry::new();
+    let started_at = Date::now();
+
+    host.register(SYSCALL_UPTIME_MS, move |s| {
+        s.push((Date::now() - started_at) as u64 as u16)
+    });
+
+    host.register(SYSCALL_UNIX_TIME, |s| {
+        let secs = (Date::now() / 1000.0) as u64;
+
+        s.push((secs >> 16) as u16)?;
+        s.push(secs as u16)
+    });
+
+    host
+}
@@ -1,6 +1,7 @@
 extern crate console_error_panic_hook;
 
 mod controller;
+mod host;
 
 #[macro_use]
 mod utils;
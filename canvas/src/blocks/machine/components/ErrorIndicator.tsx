@@ -30,6 +30,15 @@ export const ErrorIndicator = ({ error }: { error: CanvasError }) => {
       )
     }
 
+    if (reason === "Deadlock") {
+      return (
+        <pre className="text-purple-11">
+          Machines {cause.ids.join(", ")} are waiting for each other's
+          messages.
+        </pre>
+      )
+    }
+
     if (reason === "ExecutionFailed") {
       return (
         <pre>
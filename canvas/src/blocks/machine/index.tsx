@@ -21,9 +21,11 @@ export const MachineBlock = memo((props: MachineProps) => {
 
   const errored = state.status === "Invalid"
   const awaiting = state.status === "Awaiting"
+  const awaitingInput = state.status === "AwaitingInput"
   const sleeping = state.status === "Sleeping"
   const halted = state.status === "Halted"
-  const backpressuring = state.inboxSize > 50
+  const blocked = state.status === "Blocked"
+  const backpressuring = blocked || state.inboxSize > 50
   const sending = state.outboxSize >= 1
 
   const className = cn(
@@ -32,6 +34,7 @@ export const MachineBlock = memo((props: MachineProps) => {
     "flex flex-col space-y-2 text-gray-50",
     errored && "!border-red-9",
     awaiting && "!border-purple-11",
+    awaitingInput && "!border-yellow-11",
     halted && "border-gray-9",
     backpressuring && "!border-orange-9",
     sending && "border-crimson-11",
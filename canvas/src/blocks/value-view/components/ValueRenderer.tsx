@@ -92,10 +92,8 @@ export const ValueRenderer = memo((props: ValueRendererProps) => {
       let end = values.findIndex((x) => x === 0)
       if (end === -1) end = values.length
 
-      const text = values
-        .slice(0, end)
-        .map((x) => String.fromCharCode(x))
-        .join("")
+      // strings are stored as UTF-16 code units, like javascript strings.
+      const text = String.fromCharCode(...values.slice(0, end))
 
       return <div className="px-3 py-1">{text}</div>
     }
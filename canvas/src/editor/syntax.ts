@@ -10,6 +10,7 @@ const VasmLR = LRLanguage.define({
         Identifier: t.variableName,
         StringDefinition: t.definitionKeyword,
         ValueDefinition: t.definitionKeyword,
+        ErrorHandlerDefinition: t.definitionKeyword,
         LabelDefinition: t.labelName,
         Instruction: t.keyword,
         String: t.string,
@@ -127,6 +127,7 @@ const isCycleError = (error: CanvasError | null) => {
 
   return (
     cause.type === "ExecutionCycleExceeded" ||
-    cause.type === "MessageNeverReceived"
+    cause.type === "MessageNeverReceived" ||
+    cause.type === "Deadlock"
   )
 }
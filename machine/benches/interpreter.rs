@@ -0,0 +1,41 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use machine::{Execute, Machine, Op};
+
+const ITERATIONS: u16 = 10000;
+
+/// Counts up to the number of iterations.
+fn counter() -> Machine {
+    vec![
+        Op::Push(0),
+        Op::Inc,                // [2] loop
+        Op::Dup,
+        Op::Push(ITERATIONS),
+        Op::LessThan,
+        Op::JumpNotZero(2),
+        Op::Pop,
+    ].into()
+}
+
+fn instructions_benchmark(cr: &mut Criterion) {
+    let mut m = counter();
+    m.run().unwrap();
+
+    let mut group = cr.benchmark_group("instructions");
+    group.throughput(Throughput::Elements(m.cycles as u64));
+
+    for (name, enabled) in [("decode every tick", false), ("decode cache", true)] {
+        let mut template = counter();
+        template.mem.decoded.enabled = enabled;
+
+        group.bench_function(name, |b| b.iter(|| {
+            let mut m = template.clone();
+            m.run().unwrap();
+            black_box(m.cycles)
+        }));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, instructions_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,60 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use machine::canvas::Canvas;
+use machine::rewind::Rewind;
+use machine::MEMORY_SIZE;
+
+const MACHINES: usize = 50;
+
+/// Canvas with many machines that have each run a small program.
+fn canvas_with_machines() -> Canvas {
+    let mut c = Canvas::new();
+
+    for _ in 0..MACHINES {
+        let id = c.add_machine().unwrap();
+        c.load_program(id, "push 5\npush 10\nadd\n.string s \"hello\"\nload_string s").unwrap();
+    }
+
+    c.run().unwrap();
+    c
+}
+
+fn clone_benchmark(cr: &mut Criterion) {
+    let canvas = canvas_with_machines();
+
+    // Baseline: the dense buffers that every machine used to clone.
+    let dense: Vec<Vec<u16>> = (0..MACHINES).map(|_| vec![0; MEMORY_SIZE as usize]).collect();
+
+    cr.bench_function("clone dense buffers", |b| b.iter(|| black_box(dense.clone())));
+    cr.bench_function("clone canvas", |b| b.iter(|| black_box(canvas.clone())));
+}
+
+fn snapshot_benchmark(cr: &mut Criterion) {
+    let canvas = canvas_with_machines();
+
+    cr.bench_function("rewind save", |b| {
+        let mut rewind = Rewind::new();
+        rewind.save(&canvas);
+
+        b.iter(|| {
+            rewind.save(black_box(&canvas));
+            rewind.snapshots.clear();
+        })
+    });
+}
+
+fn write_benchmark(cr: &mut Criterion) {
+    let mut canvas = canvas_with_machines();
+
+    cr.bench_function("write after clone", |b| b.iter(|| {
+        let snapshot = canvas.clone();
+
+        for m in &mut canvas.seq.machines {
+            m.mem.write(0x1000, &[1, 2, 3]);
+        }
+
+        black_box(snapshot)
+    }));
+}
+
+criterion_group!(benches, clone_benchmark, snapshot_benchmark, write_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use machine::canvas::Canvas;
+
+const MACHINES: u16 = 100;
+
+/// Canvas with many machines that are busy computing.
+fn busy_canvas(threads: usize) -> Canvas {
+    let mut c = Canvas::new();
+
+    for _ in 0..MACHINES {
+        let id = c.add_machine().unwrap();
+        c.load_program(id, "push 0\nloop:\n dup\n push 7\n xor\n nip\n jump loop").unwrap();
+    }
+
+    c.machine_cycle_per_tick = 2000;
+    c.seq.threads = threads;
+    c.seq.ready();
+    c
+}
+
+fn step_benchmark(cr: &mut Criterion) {
+    let threads = std::thread::available_parallelism().map_or(4, |n| n.get());
+
+    let mut serial = busy_canvas(1);
+    let mut parallel = busy_canvas(threads);
+
+    cr.bench_function("tick serial", |b| b.iter(|| black_box(serial.tick(1))));
+    cr.bench_function("tick parallel", |b| b.iter(|| black_box(parallel.tick(1))));
+}
+
+criterion_group!(benches, step_benchmark);
+criterion_main!(benches);
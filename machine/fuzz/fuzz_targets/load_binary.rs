@@ -0,0 +1,9 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use machine::binary::bytes::u8_vec_to_u16;
+use machine::run::load_from_binary;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = load_from_binary(&u8_vec_to_u16(data.to_vec()));
+});
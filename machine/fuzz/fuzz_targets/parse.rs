@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use machine::{Machine, Scanner};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = std::str::from_utf8(data) else { return; };
+
+    let mut scanner = Scanner::new(source);
+    let _ = scanner.scan_tokens();
+
+    // Assemble and load the program, as the editor does on every keystroke.
+    let _: Result<Machine, _> = source.try_into();
+});
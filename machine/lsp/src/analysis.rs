@@ -0,0 +1,370 @@
+use std::str::FromStr;
+use lsp_types::{
+    CompletionItem, CompletionItemKind, Diagnostic, DiagnosticSeverity, Documentation, Hover, HoverContents,
+    MarkupContent, MarkupKind, Position, Range, TextEdit,
+};
+use machine::{is_identifier, Op, Parser, Scanner, Token, TokenType};
+use strum::VariantNames;
+
+/// Directives that start a definition.
+const DIRECTIVES: [(&str, &str); 3] = [
+    (".string", "Define a null-terminated string in the data segment: `.string name \"text\"`"),
+    (".value", "Define a value in the data segment: `.value name 0xFF`"),
+    (".on_error", "Jump to the label when a runtime error occurs: `.on_error handler`"),
+];
+
+/// An open source file, along with its tokens.
+pub struct Document {
+    pub text: String,
+
+    /// Tokens of the source. Empty if the source cannot be scanned.
+    tokens: Vec<Token>,
+}
+
+/// Where a symbol appears in the source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Occurrence {
+    pub range: Range,
+
+    /// Is this the definition of the symbol?
+    pub is_definition: bool,
+}
+
+impl Document {
+    pub fn new(text: &str) -> Document {
+        let mut scanner = Scanner::new(text);
+        let tokens = if scanner.scan_tokens().is_ok() { scanner.tokens } else { vec![] };
+
+        Document { text: text.into(), tokens }
+    }
+
+    /// Errors from the scanner and the parser, and warnings from the stack verifier.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut scanner = Scanner::new(&self.text);
+
+        if let Err(error) = scanner.scan_tokens() {
+            let start = self.position(scanner.line, scanner.column);
+            return vec![diagnostic(Range::new(start, start), DiagnosticSeverity::ERROR, error.to_string())];
+        }
+
+        let mut parser = Parser::new(&self.text);
+
+        if let Err(error) = parser.parse() {
+            let range = parser.current_token().map_or(Range::default(), |token| self.token_range(token));
+            return vec![diagnostic(range, DiagnosticSeverity::ERROR, error.to_string())];
+        }
+
+        parser.verify().into_iter().map(|warning| {
+            // Warnings count lines from 1.
+            let line = warning.line().saturating_sub(1);
+            let end = self.position(line, self.line_text(line).chars().count());
+
+            diagnostic(Range::new(self.position(line, 0), end), DiagnosticSeverity::WARNING, warning.to_string())
+        }).collect()
+    }
+
+    /// Instruction mnemonics, directives and the symbols defined in the source.
+    pub fn completions(&self) -> Vec<CompletionItem> {
+        let mut items = vec![];
+
+        for name in Op::VARIANTS.iter().filter(|name| !matches!(**name, "noop" | "eof")) {
+            let Ok(op) = Op::from_str(name) else { continue; };
+
+            items.push(CompletionItem {
+                label: name.to_string(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                detail: Some(signature(&op)),
+                documentation: Some(Documentation::String(op.docs().into())),
+                ..Default::default()
+            });
+        }
+
+        for (name, docs) in DIRECTIVES {
+            items.push(CompletionItem {
+                label: name.into(),
+                kind: Some(CompletionItemKind::KEYWORD),
+                documentation: Some(Documentation::String(docs.into())),
+                ..Default::default()
+            });
+        }
+
+        for (index, token) in self.tokens.iter().enumerate() {
+            if !self.is_definition(index) { continue; }
+
+            let kind = match token.token_type {
+                TokenType::LabelDefinition => CompletionItemKind::FUNCTION,
+                _ => CompletionItemKind::VARIABLE,
+            };
+
+            items.push(CompletionItem { label: symbol_name(token).into(), kind: Some(kind), ..Default::default() });
+        }
+
+        items
+    }
+
+    /// Docs of the instruction, or the definition of the symbol under the cursor.
+    pub fn hover(&self, position: Position) -> Option<Hover> {
+        let index = self.token_at(position)?;
+        let token = &self.tokens[index];
+
+        let text = match token.token_type {
+            TokenType::Instruction => {
+                let op = Op::from_str(token.lexeme.trim()).ok()?;
+                let mut text = format!("```asm\n{}\n```\n\n{}", signature(&op), op.docs());
+
+                if let Some(effect) = op.stack_effect() {
+                    text.push_str(&format!("\n\nPops {}, pushes {}.", effect.pops, effect.pushes));
+                }
+
+                text
+            }
+
+            TokenType::StringDefinition | TokenType::ValueDefinition | TokenType::ErrorHandlerDefinition => {
+                let (_, docs) = DIRECTIVES.iter().find(|(name, _)| *name == token.lexeme.trim())?;
+                docs.to_string()
+            }
+
+            TokenType::LabelDefinition | TokenType::Identifier => {
+                let definition = self.occurrences(symbol_name(token)).into_iter().find(|o| o.is_definition)?;
+                let line = self.line_text(definition.range.start.line as usize).trim();
+
+                format!("```asm\n{line}\n```")
+            }
+
+            _ => return None,
+        };
+
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent { kind: MarkupKind::Markdown, value: text }),
+            range: Some(self.token_range(token)),
+        })
+    }
+
+    /// Range of the definition of the symbol under the cursor.
+    pub fn definition(&self, position: Position) -> Option<Range> {
+        let name = self.symbol_at(position)?;
+        self.occurrences(name).into_iter().find(|o| o.is_definition).map(|o| o.range)
+    }
+
+    /// Ranges of every use of the symbol under the cursor.
+    pub fn references(&self, position: Position, include_definition: bool) -> Vec<Range> {
+        let Some(name) = self.symbol_at(position) else { return vec![]; };
+
+        self.occurrences(name).into_iter()
+            .filter(|o| include_definition || !o.is_definition)
+            .map(|o| o.range)
+            .collect()
+    }
+
+    /// Edits that rename the symbol under the cursor, or an error if the new name is invalid.
+    pub fn rename(&self, position: Position, new_name: &str) -> Result<Vec<TextEdit>, String> {
+        let name = self.symbol_at(position).ok_or("there is no symbol to rename")?;
+
+        let starts_with_letter = new_name.chars().next().is_some_and(|c| !c.is_ascii_digit());
+
+        if !starts_with_letter || !new_name.chars().all(is_identifier) {
+            return Err(format!("'{new_name}' is not a valid symbol name"));
+        }
+
+        if Op::from_str(new_name).is_ok() {
+            return Err(format!("'{new_name}' is an instruction"));
+        }
+
+        Ok(self.occurrences(name).into_iter().map(|o| TextEdit::new(o.range, new_name.into())).collect())
+    }
+
+    /// Every occurrence of the symbol. The colon of a label definition is not part of its range.
+    pub fn occurrences(&self, name: &str) -> Vec<Occurrence> {
+        self.tokens.iter().enumerate()
+            .filter(|(_, token)| is_symbol(token) && symbol_name(token) == name)
+            .map(|(index, token)| {
+                let start = self.position(token.line, token.column);
+                let end = self.position(token.line, token.column + name.chars().count());
+
+                Occurrence { range: Range::new(start, end), is_definition: self.is_definition(index) }
+            })
+            .collect()
+    }
+
+    /// Name of the symbol under the cursor.
+    fn symbol_at(&self, position: Position) -> Option<&str> {
+        let token = &self.tokens[self.token_at(position)?];
+        if !is_symbol(token) { return None; }
+
+        Some(symbol_name(token))
+    }
+
+    /// Is the token a label definition, or the name in a `.string` or `.value` definition?
+    fn is_definition(&self, index: usize) -> bool {
+        let token = &self.tokens[index];
+        if token.token_type == TokenType::LabelDefinition { return true; }
+
+        let previous = index.checked_sub(1).and_then(|i| self.tokens.get(i));
+        let defines = matches!(previous.map(|t| &t.token_type), Some(TokenType::StringDefinition | TokenType::ValueDefinition));
+
+        token.token_type == TokenType::Identifier && defines
+    }
+
+    /// Index of the token under the cursor. The position right after a token also counts.
+    fn token_at(&self, position: Position) -> Option<usize> {
+        let line = position.line as usize;
+        let column = self.column(line, position.character);
+
+        self.tokens.iter().position(|token| {
+            let length = token.lexeme.trim_end().chars().count();
+            token.line == line && token.column <= column && column <= token.column + length
+        })
+    }
+
+    fn token_range(&self, token: &Token) -> Range {
+        let length = token.lexeme.trim_end().chars().count();
+        Range::new(self.position(token.line, token.column), self.position(token.line, token.column + length))
+    }
+
+    fn line_text(&self, line: usize) -> &str {
+        self.text.split('\n').nth(line).unwrap_or("")
+    }
+
+    /// Position of the character column on the line. Editors count columns in UTF-16 code units.
+    fn position(&self, line: usize, column: usize) -> Position {
+        let character: usize = self.line_text(line).chars().take(column).map(char::len_utf16).sum();
+        Position::new(line as u32, character as u32)
+    }
+
+    /// Character column of the UTF-16 position on the line.
+    fn column(&self, line: usize, character: u32) -> usize {
+        let mut units = 0;
+
+        for (column, c) in self.line_text(line).chars().enumerate() {
+            if units >= character as usize { return column; }
+            units += c.len_utf16();
+        }
+
+        self.line_text(line).chars().count()
+    }
+}
+
+fn diagnostic(range: Range, severity: DiagnosticSeverity, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(severity),
+        source: Some("machine".into()),
+        message,
+        ..Default::default()
+    }
+}
+
+/// Mnemonic of the instruction, followed by its operands, e.g. `send _ _`
+fn signature(op: &Op) -> String {
+    let mut text = op.to_string();
+    (0..op.arity()).for_each(|_| text.push_str(" _"));
+    text
+}
+
+fn is_symbol(token: &Token) -> bool {
+    matches!(token.token_type, TokenType::LabelDefinition | TokenType::Identifier)
+}
+
+/// Name of the symbol, without the colon of a label definition.
+fn symbol_name(token: &Token) -> &str {
+    let name = token.lexeme.trim();
+    name.strip_suffix(':').unwrap_or(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#".string greeting "héllo"
+jump start
+
+print_twice:
+    load_string greeting
+    print
+    return
+
+start:
+    call print_twice
+    call print_twice
+"#;
+
+    fn range(line: u32, start: u32, end: u32) -> Range {
+        Range::new(Position::new(line, start), Position::new(line, end))
+    }
+
+    #[test]
+    fn test_definition() {
+        let doc = Document::new(SOURCE);
+
+        assert_eq!(doc.definition(Position::new(9, 10)), Some(range(3, 0, 11)));
+        assert_eq!(doc.definition(Position::new(4, 18)), Some(range(0, 8, 16)));
+        assert_eq!(doc.definition(Position::new(5, 5)), None);
+    }
+
+    #[test]
+    fn test_references() {
+        let doc = Document::new(SOURCE);
+
+        assert_eq!(doc.references(Position::new(3, 2), false), [range(9, 9, 20), range(10, 9, 20)]);
+        assert_eq!(doc.references(Position::new(1, 7), true), [range(1, 5, 10), range(8, 0, 5)]);
+    }
+
+    #[test]
+    fn test_rename() {
+        let doc = Document::new(SOURCE);
+        let edits = doc.rename(Position::new(8, 1), "main").unwrap();
+
+        assert_eq!(edits, [TextEdit::new(range(1, 5, 10), "main".into()), TextEdit::new(range(8, 0, 5), "main".into())]);
+        assert!(doc.rename(Position::new(8, 1), "9lives").is_err());
+        assert!(doc.rename(Position::new(8, 1), "push").is_err());
+    }
+
+    #[test]
+    fn test_hover() {
+        let doc = Document::new(SOURCE);
+
+        let Some(Hover { contents: HoverContents::Markup(hover), .. }) = doc.hover(Position::new(5, 6)) else {
+            panic!("expected a hover for the instruction");
+        };
+
+        assert!(hover.value.contains("Print the text at the memory address of operand."));
+
+        let Some(Hover { contents: HoverContents::Markup(hover), .. }) = doc.hover(Position::new(4, 20)) else {
+            panic!("expected a hover for the symbol");
+        };
+
+        assert!(hover.value.contains(r#".string greeting "héllo""#));
+    }
+
+    #[test]
+    fn test_completions() {
+        let labels: Vec<String> = Document::new(SOURCE).completions().into_iter().map(|item| item.label).collect();
+
+        assert!(labels.contains(&"jump_not_zero".to_string()));
+        assert!(labels.contains(&".string".to_string()));
+        assert!(labels.contains(&"print_twice".to_string()));
+        assert!(labels.contains(&"greeting".to_string()));
+        assert!(!labels.contains(&"eof".to_string()));
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        assert_eq!(Document::new(SOURCE).diagnostics(), []);
+
+        let errors = Document::new("push 1\npusj 2").diagnostics();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].range, range(1, 0, 4));
+        assert_eq!(errors[0].severity, Some(DiagnosticSeverity::ERROR));
+
+        let warnings = Document::new("push 1\nadd").diagnostics();
+        assert_eq!(warnings[0].range, range(1, 0, 3));
+        assert_eq!(warnings[0].severity, Some(DiagnosticSeverity::WARNING));
+    }
+
+    #[test]
+    fn test_utf16_positions() {
+        let doc = Document::new("    ; é\n    .string ü \"ö\"\n    load_string ü");
+
+        assert_eq!(doc.definition(Position::new(2, 16)), Some(range(1, 12, 13)));
+    }
+}
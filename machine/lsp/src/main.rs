@@ -0,0 +1,187 @@
+mod analysis;
+
+use std::collections::HashMap;
+use std::error::Error;
+use lsp_server::{Connection, ErrorCode, ExtractError, Message, Notification, Request, Response};
+use lsp_types::notification::{DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _, PublishDiagnostics};
+use lsp_types::request::{Completion, GotoDefinition, HoverRequest, References, Rename, Request as _};
+use lsp_types::{
+    CompletionOptions, CompletionResponse, GotoDefinitionResponse, HoverProviderCapability, Location, OneOf,
+    PublishDiagnosticsParams, ServerCapabilities, TextDocumentSyncCapability, TextDocumentSyncKind, Url, WorkspaceEdit,
+};
+use serde::de::DeserializeOwned;
+use analysis::Document;
+
+type Errorable = Result<(), Box<dyn Error + Sync + Send>>;
+
+/// Language server for the assembly dialect, over the stdio transport.
+fn main() -> Errorable {
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        completion_provider: Some(CompletionOptions::default()),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        rename_provider: Some(OneOf::Left(true)),
+        ..Default::default()
+    };
+
+    connection.initialize(serde_json::to_value(capabilities)?)?;
+    Server::default().run(&connection)?;
+
+    // The writer thread stops once the connection is dropped.
+    drop(connection);
+    io_threads.join()?;
+
+    Ok(())
+}
+
+/// Keeps the open documents, and answers the requests of the editor.
+#[derive(Default)]
+struct Server {
+    documents: HashMap<Url, Document>,
+}
+
+impl Server {
+    fn run(&mut self, connection: &Connection) -> Errorable {
+        for message in &connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if connection.handle_shutdown(&request)? { return Ok(()); }
+
+                    let response = self.handle_request(request);
+                    connection.sender.send(Message::Response(response))?;
+                }
+
+                Message::Notification(notification) => {
+                    if let Some(uri) = self.handle_notification(notification) {
+                        self.publish_diagnostics(connection, uri)?;
+                    }
+                }
+
+                Message::Response(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn handle_request(&self, request: Request) -> Response {
+        let id = request.id.clone();
+
+        let result = match request.method.as_str() {
+            HoverRequest::METHOD => params::<HoverRequest>(request).map(|p| {
+                let p = p.text_document_position_params;
+                json(self.document(&p.text_document.uri).and_then(|doc| doc.hover(p.position)))
+            }),
+
+            Completion::METHOD => params::<Completion>(request).map(|p| {
+                let items = self.document(&p.text_document_position.text_document.uri).map(|doc| doc.completions());
+                json(items.map(CompletionResponse::Array))
+            }),
+
+            GotoDefinition::METHOD => params::<GotoDefinition>(request).map(|p| {
+                let p = p.text_document_position_params;
+                let uri = p.text_document.uri;
+
+                let range = self.document(&uri).and_then(|doc| doc.definition(p.position));
+                json(range.map(|range| GotoDefinitionResponse::Scalar(Location::new(uri, range))))
+            }),
+
+            References::METHOD => params::<References>(request).map(|p| {
+                let uri = p.text_document_position.text_document.uri;
+                let position = p.text_document_position.position;
+
+                let ranges = self.document(&uri).map(|doc| doc.references(position, p.context.include_declaration));
+                json(ranges.map(|ranges| ranges.into_iter().map(|range| Location::new(uri.clone(), range)).collect::<Vec<_>>()))
+            }),
+
+            Rename::METHOD => params::<Rename>(request).and_then(|p| {
+                let uri = p.text_document_position.text_document.uri;
+                let position = p.text_document_position.position;
+
+                let Some(doc) = self.document(&uri) else { return Ok(json(None::<WorkspaceEdit>)); };
+
+                match doc.rename(position, &p.new_name) {
+                    Ok(edits) => Ok(json(WorkspaceEdit::new(HashMap::from([(uri, edits)])))),
+                    Err(message) => Err((ErrorCode::InvalidParams, message)),
+                }
+            }),
+
+            _ => Err((ErrorCode::MethodNotFound, format!("unknown method '{}'", request.method))),
+        };
+
+        match result {
+            Ok(value) => Response::new_ok(id, value),
+            Err((code, message)) => Response::new_err(id, code as i32, message),
+        }
+    }
+
+    /// Update the documents. Returns the document whose diagnostics should be published.
+    fn handle_notification(&mut self, notification: Notification) -> Option<Url> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let p = notification_params::<DidOpenTextDocument>(notification)?;
+                let uri = p.text_document.uri;
+
+                self.documents.insert(uri.clone(), Document::new(&p.text_document.text));
+                Some(uri)
+            }
+
+            DidChangeTextDocument::METHOD => {
+                let p = notification_params::<DidChangeTextDocument>(notification)?;
+                let uri = p.text_document.uri;
+
+                // The server asks for full document sync, so the last change holds the whole text.
+                let change = p.content_changes.into_iter().last()?;
+                self.documents.insert(uri.clone(), Document::new(&change.text));
+                Some(uri)
+            }
+
+            DidCloseTextDocument::METHOD => {
+                let p = notification_params::<DidCloseTextDocument>(notification)?;
+                self.documents.remove(&p.text_document.uri);
+
+                // Clear the diagnostics of the closed document.
+                Some(p.text_document.uri)
+            }
+
+            _ => None,
+        }
+    }
+
+    fn publish_diagnostics(&self, connection: &Connection, uri: Url) -> Errorable {
+        let diagnostics = self.document(&uri).map(|doc| doc.diagnostics()).unwrap_or_default();
+        let params = PublishDiagnosticsParams::new(uri, diagnostics, None);
+
+        let notification = Notification::new(PublishDiagnostics::METHOD.into(), params);
+        connection.sender.send(Message::Notification(notification))?;
+
+        Ok(())
+    }
+
+    fn document(&self, uri: &Url) -> Option<&Document> {
+        self.documents.get(uri)
+    }
+}
+
+/// Parameters of the request, or an error response if they are malformed.
+fn params<R: lsp_types::request::Request>(request: Request) -> Result<R::Params, (ErrorCode, String)>
+    where R::Params: DeserializeOwned {
+    match request.extract::<R::Params>(R::METHOD) {
+        Ok((_, params)) => Ok(params),
+        Err(ExtractError::JsonError { error, .. }) => Err((ErrorCode::InvalidParams, error.to_string())),
+        Err(ExtractError::MethodMismatch(request)) => Err((ErrorCode::MethodNotFound, request.method)),
+    }
+}
+
+fn notification_params<N: lsp_types::notification::Notification>(notification: Notification) -> Option<N::Params>
+    where N::Params: DeserializeOwned {
+    notification.extract(N::METHOD).ok()
+}
+
+fn json<T: serde::Serialize>(value: T) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or(serde_json::Value::Null)
+}
@@ -0,0 +1,54 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Expr, ExprLit, Lit, Meta};
+
+/// Join the doc comment lines of the attributes.
+fn doc_comment(attrs: &[Attribute]) -> String {
+    let lines: Vec<String> = attrs.iter().filter_map(|attr| {
+        let Meta::NameValue(meta) = &attr.meta else { return None; };
+        if !meta.path.is_ident("doc") { return None; }
+
+        match &meta.value {
+            Expr::Lit(ExprLit { lit: Lit::Str(text), .. }) => Some(text.value().trim().to_string()),
+            _ => None,
+        }
+    }).collect();
+
+    lines.join("\n")
+}
+
+pub fn insert_docs_method(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+
+    // Ensure that the input is an enum
+    if let Data::Enum(data_enum) = &ast.data {
+        let enum_name = &ast.ident;
+
+        let docs = data_enum.variants.iter().map(|variant| {
+            let variant_ident = &variant.ident;
+            let docs = doc_comment(&variant.attrs);
+
+            quote! {
+                #enum_name::#variant_ident { .. } => #docs
+            }
+        });
+
+        // Insert the docs method into the enum.
+        let expanded = quote! {
+            impl #enum_name {
+                /// Doc comment of the variant, or an empty string if it has none.
+                pub fn docs(&self) -> &'static str {
+                    match self {
+                        #(#docs,)*
+                    }
+                }
+            }
+        };
+
+        expanded.into()
+    } else {
+        panic!("Docs can only be derived for enums");
+    }
+}
@@ -7,11 +7,15 @@ mod arity;
 mod with_arg;
 mod field_values;
 mod variant_index;
+mod stack_effect;
+mod docs;
 
 use arity::insert_arity_method;
 use with_arg::insert_arg_method;
 use field_values::insert_field_values_method;
 use variant_index::insert_variant_index_method;
+use stack_effect::insert_stack_effect_method;
+use docs::insert_docs_method;
 
 #[proc_macro_derive(Arity)]
 pub fn derive_arity(input: TokenStream) -> TokenStream {
@@ -32,3 +36,13 @@ pub fn derive_field_values(input: TokenStream) -> TokenStream {
 pub fn derive_variant_index(input: TokenStream) -> TokenStream {
     insert_variant_index_method(input)
 }
+
+#[proc_macro_derive(StackEffect, attributes(stack))]
+pub fn derive_stack_effect(input: TokenStream) -> TokenStream {
+    insert_stack_effect_method(input)
+}
+
+#[proc_macro_derive(Docs)]
+pub fn derive_docs(input: TokenStream) -> TokenStream {
+    insert_docs_method(input)
+}
@@ -0,0 +1,118 @@
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Data, DeriveInput, Error, Expr, Variant};
+
+use crate::enums::variant_arity;
+
+/// The stack effect declared by the `#[stack(...)]` attribute of a variant.
+enum Declared {
+    /// The effect depends on runtime values, e.g. `#[stack(unknown)]`
+    Unknown,
+
+    /// The number of values popped and pushed, e.g. `#[stack(pop = 2, push = 1)]`
+    Known { pop: Expr, push: Expr },
+}
+
+/// Parse the `#[stack(...)]` attribute of the variant.
+fn declared_effect(variant: &Variant) -> syn::Result<Declared> {
+    let Some(attr) = variant.attrs.iter().find(|attr| attr.path().is_ident("stack")) else {
+        return Err(Error::new_spanned(
+            &variant.ident,
+            "missing #[stack(pop = .., push = ..)] or #[stack(unknown)] attribute",
+        ));
+    };
+
+    let mut unknown = false;
+    let mut pop = None;
+    let mut push = None;
+
+    attr.parse_nested_meta(|meta| {
+        if meta.path.is_ident("unknown") {
+            unknown = true;
+            return Ok(());
+        }
+
+        if meta.path.is_ident("pop") {
+            pop = Some(meta.value()?.parse::<Expr>()?);
+            return Ok(());
+        }
+
+        if meta.path.is_ident("push") {
+            push = Some(meta.value()?.parse::<Expr>()?);
+            return Ok(());
+        }
+
+        Err(meta.error("expected `pop`, `push` or `unknown`"))
+    })?;
+
+    match (unknown, pop, push) {
+        (true, None, None) => Ok(Declared::Unknown),
+        (false, Some(pop), Some(push)) => Ok(Declared::Known { pop, push }),
+        _ => Err(Error::new_spanned(
+            attr,
+            "expected either both `pop` and `push`, or `unknown`",
+        )),
+    }
+}
+
+pub fn insert_stack_effect_method(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+
+    // Ensure that the input is an enum
+    if let Data::Enum(data_enum) = &ast.data {
+        let enum_name = &ast.ident;
+        let mut arms = vec![];
+
+        for variant in &data_enum.variants {
+            let declared = match declared_effect(variant) {
+                Ok(declared) => declared,
+                Err(error) => return error.to_compile_error().into(),
+            };
+
+            let field_count = variant_arity(variant);
+            let variant_ident = &variant.ident;
+
+            // Bind the fields as f0, f1, ... so the attribute can refer to them.
+            let field_vars: Vec<_> = (0..field_count).map(|i| format_ident!("f{}", i)).collect();
+
+            let pattern = if field_count == 0 {
+                quote! { #enum_name::#variant_ident }
+            } else {
+                quote! { #enum_name::#variant_ident(#(#field_vars,)*) }
+            };
+
+            let body = match declared {
+                Declared::Unknown => quote! { None },
+
+                // Widen the fields, so that `1 + f0` cannot overflow.
+                Declared::Known { pop, push } => quote! {{
+                    #(let #field_vars = #field_vars as u32;)*
+
+                    Some(StackEffect { pops: #pop, pushes: #push })
+                }},
+            };
+
+            arms.push(quote! { #pattern => #body });
+        }
+
+        // Insert the stack effect method into the enum.
+        let expanded = quote! {
+            impl #enum_name {
+                /// How many values the operation pops from and pushes onto the stack.
+                /// Returns None if it depends on the runtime values.
+                #[allow(unused_variables)]
+                pub fn stack_effect(&self) -> Option<StackEffect> {
+                    match *self {
+                        #(#arms,)*
+                    }
+                }
+            }
+        };
+
+        expanded.into()
+    } else {
+        panic!("Stack effect can only be derived for enums");
+    }
+}
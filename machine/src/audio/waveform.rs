@@ -78,6 +78,7 @@ pub fn generate_waveform(waveform: Waveform, time: u16) -> u16 {
         Waveform::Tangent => tangent_wave(time),
         Waveform::Sawtooth => sawtooth_wave(time),
         Waveform::Triangle => triangle_wave(time),
+        // Noise depends on the canvas' random number generator, see `Canvas::tick_osc_block`.
         Waveform::Noise => 0,
     }
 }
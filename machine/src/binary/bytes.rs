@@ -5,9 +5,10 @@ pub fn u16_to_u8_bytes(input: u16) -> [u8; 2] {
     [high_byte, low_byte]
 }
 
+/// Missing bytes are read as zero.
 pub fn u8_bytes_to_u16(bytes: &[u8]) -> u16 {
-    let high_byte = (bytes[0] as u16) << 8;
-    let low_byte = bytes[1] as u16;
+    let high_byte = (bytes.first().copied().unwrap_or(0) as u16) << 8;
+    let low_byte = bytes.get(1).copied().unwrap_or(0) as u16;
     high_byte | low_byte
 }
 
@@ -18,6 +18,11 @@ pub fn compile_to_bytecode(ops: Vec<Op>) -> Vec<u16> {
 pub fn compile_to_binary(source: &str) -> Result<Vec<u16>, ParseError> {
     let parser: Parser = (*source).try_into()?;
 
+    Ok(compile_parser_to_binary(parser))
+}
+
+/// Pack the operations and symbols of the parsed program into a binary.
+pub fn compile_parser_to_binary(parser: Parser) -> Vec<u16> {
     // [code_start, code_size, data_start, data_size]
     let mut header: [u16; 4] = [0x00, 0x00, 0x00, 0x00];
 
@@ -40,7 +45,7 @@ pub fn compile_to_binary(source: &str) -> Result<Vec<u16>, ParseError> {
     bytes.extend(code_segment);
     bytes.extend(data_segment);
 
-    Ok(bytes)
+    bytes
 }
 
 #[cfg(test)]
@@ -1,15 +1,15 @@
 use snafu::ensure;
-use crate::{Machine, CODE_START, DATA_START};
-use crate::cli::cli_error::{IncorrectMagicBytesSnafu};
+use crate::Machine;
+use crate::cli::cli_error::{IncorrectFileHeaderSnafu, IncorrectMagicBytesSnafu};
 use crate::cli::CLIError;
 use crate::cli::CLIError::IncorrectFileHeader;
 use super::compile::MAGIC_BYTES;
 
 pub fn load_from_binary(bytes: &[u16]) -> Result<Machine, CLIError> {
     // Verify magic bytes at the beginning of file.
-    ensure!(bytes[0..2] == MAGIC_BYTES[0..2], IncorrectMagicBytesSnafu);
+    ensure!(bytes.get(0..2) == Some(&MAGIC_BYTES[..]), IncorrectMagicBytesSnafu);
 
-    let header: Vec<usize> = bytes[2..6].iter().map(|&x| x as usize).collect();
+    let header: Vec<usize> = bytes.get(2..6).ok_or(IncorrectFileHeader)?.iter().map(|&x| x as usize).collect();
 
     // Read header from binary.
     let [code_ptr, code_len, data_ptr, data_len] = header[..] else {
@@ -17,12 +17,16 @@ pub fn load_from_binary(bytes: &[u16]) -> Result<Machine, CLIError> {
     };
 
     // Read segments from binary.
-    let code_bytes = bytes[code_ptr..(code_ptr + code_len)].to_vec();
-    let data_bytes = bytes[data_ptr..(data_ptr + data_len)].to_vec();
+    let code_bytes = bytes.get(code_ptr..(code_ptr + code_len)).ok_or(IncorrectFileHeader)?;
+    let data_bytes = bytes.get(data_ptr..(data_ptr + data_len)).ok_or(IncorrectFileHeader)?;
 
-    // Load the segments into memory.
+    // The segments must fit in the memory layout.
     let mut m = Machine::new();
-    m.mem.write(CODE_START, &code_bytes);
-    m.mem.write(DATA_START, &data_bytes);
+    let layout = m.mem.layout;
+    ensure!(code_len <= layout.code_size as usize && data_len <= layout.data_size as usize, IncorrectFileHeaderSnafu);
+
+    // Load the segments into memory.
+    m.mem.write(layout.code_start(), code_bytes);
+    m.mem.write(layout.data_start(), data_bytes);
     Ok(m)
 }
@@ -1,6 +1,6 @@
-use std::collections::VecDeque;
 use serde::{Deserialize, Serialize};
 use crate::{Event, Message};
+use crate::canvas::Mailbox;
 use crate::audio::waveform::Waveform;
 use strum_macros::{EnumIs};
 use tsify::Tsify;
@@ -15,7 +15,7 @@ pub struct Block {
 
     pub data: BlockData,
 
-    pub inbox: VecDeque<Message>,
+    pub inbox: Mailbox,
     pub outbox: Vec<Message>,
     pub events: Vec<Event>,
 }
@@ -117,10 +117,10 @@ pub enum BlockData {
 
 impl Block {
     pub fn new(id: u16, data: BlockData) -> Block {
-        Block { id, data, inbox: VecDeque::new(), outbox: vec![], events: vec![] }
+        Block { id, data, inbox: Mailbox::new(), outbox: vec![], events: vec![] }
     }
 
     pub fn consume_messages(&mut self) -> Vec<Message> {
-        self.inbox.drain(..).collect()
+        self.inbox.drain()
     }
 }
@@ -4,8 +4,6 @@ use crate::{Action, Message};
 use crate::blocks::BlockData::Memory;
 use crate::canvas::virtual_io::{read_from_address, write_to_address};
 
-const REQUEST_DATA: u16 = 0x1EE;
-
 impl Canvas {
     pub fn tick_memory_block(&mut self, id: u16, messages: Vec<Message>) -> Errorable {
         for message in messages {
@@ -23,18 +21,6 @@ impl Canvas {
 
                 Action::Write { address, data } => {
                     let Memory { values, .. } = &mut self.mut_block(id)?.data else { continue; };
-
-                    // HACK: this is a special address that requests data from the block.
-                    //       our mapped port can only handle up to 0x200 addresses.
-                    // TODO: implement a paged memory system.
-                    if address == REQUEST_DATA {
-                        if let [offset, count] = data[..] {
-                            let action = read_from_address(offset, count, &values);
-                            self.send_direct_message(id, message.sender.block, action)?;
-                            continue;
-                        }
-                    }
-
                     write_to_address(address, data, values);
                 }
 
@@ -34,6 +34,9 @@ impl Canvas {
     }
 
     fn generate_waveform(&mut self, waveform: Waveform, time: u16) -> u16 {
-        self.wavetable.get(waveform, time)
+        match waveform {
+            Waveform::Noise => self.rng.range(0, 256),
+            _ => self.wavetable.get(waveform, time),
+        }
     }
 }
\ No newline at end of file
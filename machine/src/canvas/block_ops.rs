@@ -4,7 +4,9 @@ use crate::blocks::{Block, BlockData};
 use crate::canvas::{Canvas, CanvasError};
 use crate::blocks::BlockData::{Machine, Memory};
 use crate::canvas::canvas::Errorable;
-use crate::canvas::CanvasError::{BlockNotFound};
+use crate::canvas::CanvasError::{BlockNotFound, MachineError, MachineNotFound};
+use crate::canvas::{Mailbox, MailboxOrder, OverflowPolicy};
+use crate::mem::MemoryLayout;
 use crate::canvas::{BlockIdInUseSnafu, MachineNotFoundSnafu};
 
 impl Canvas {
@@ -73,12 +75,59 @@ impl Canvas {
         Ok(())
     }
 
+    /// Add a machine with a custom memory layout.
+    pub fn add_machine_with_layout(&mut self, layout: MemoryLayout) -> Result<u16, CanvasError> {
+        let id = self.block_id();
+
+        self.seq.add_with_layout(id, layout).map_err(|cause| MachineError { cause })?;
+        self.add_block_with_id(id, Machine { machine_id: id })?;
+
+        Ok(id)
+    }
+
+    /// Returns the mailbox of the block.
+    /// Messages sent to a machine block are delivered to the machine's mailbox.
+    pub fn mailbox(&self, id: u16) -> Result<&Mailbox, CanvasError> {
+        let block = self.get_block(id)?;
+
+        if let Machine { machine_id } = block.data {
+            let machine = self.seq.get(machine_id).ok_or(MachineNotFound { id: machine_id })?;
+            return Ok(&machine.inbox);
+        }
+
+        Ok(&block.inbox)
+    }
+
+    pub fn mailbox_mut(&mut self, id: u16) -> Result<&mut Mailbox, CanvasError> {
+        if let Machine { machine_id } = self.get_block(id)?.data {
+            let machine = self.seq.get_mut(machine_id).ok_or(MachineNotFound { id: machine_id })?;
+            return Ok(&mut machine.inbox);
+        }
+
+        Ok(&mut self.mut_block(id)?.inbox)
+    }
+
+    /// Set the order in which the block takes its incoming messages.
+    pub fn set_mailbox_order(&mut self, id: u16, order: MailboxOrder) -> Errorable {
+        self.mailbox_mut(id)?.order = order;
+        Ok(())
+    }
+
+    /// Set what happens to the messages that arrive when the block's inbox is full.
+    pub fn set_overflow_policy(&mut self, id: u16, policy: OverflowPolicy) -> Errorable {
+        self.mailbox_mut(id)?.overflow = policy;
+        Ok(())
+    }
+
     pub fn update_block(&mut self, id: u16, data: BlockData) -> Errorable {
         self.mut_block(id)?.data = data;
         Ok(())
     }
 
     pub fn reset_blocks(&mut self) -> Errorable {
+        // Replay the same random sequence after a reset.
+        self.rng.reset();
+
         // Collect the ids of the blocks that we can reset.
         // Machine block is handled separately, so we don't need to tick them.
         let ids: Vec<_> = self.blocks.iter().filter(|b| !b.data.is_machine()).map(|b| b.id).collect();
@@ -1,6 +1,8 @@
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use crate::{Sequencer};
 use crate::audio::wavetable::Wavetable;
+use crate::random::Random;
 use crate::blocks::{Block};
 use super::canvas_error::{CanvasError};
 use super::wire::{Wire};
@@ -22,6 +24,12 @@ pub struct Canvas {
     /// How many messages can the inbox hold before it starts dropping messages?
     pub inbox_limit: usize,
 
+    /// How many messages were dropped on each wire, as the recipient's inbox is full?
+    pub wire_dropped: HashMap<u16, u32>,
+
+    /// Random number generator for the blocks, e.g. the noise oscillator.
+    pub rng: Random,
+
     /// Used for pre-computing waveforms for performance.
     #[serde(skip)]
     pub wavetable: Wavetable,
@@ -35,11 +43,13 @@ impl Canvas {
 
             seq: Sequencer::new(),
             wavetable: Wavetable::new(),
+            rng: Random::default(),
 
             block_id_counter: 0,
             wire_id_counter: 0,
 
             inbox_limit: 100,
+            wire_dropped: HashMap::new(),
             machine_cycle_per_tick: 1,
         }
     }
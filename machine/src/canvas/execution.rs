@@ -1,14 +1,19 @@
 use std::collections::HashMap;
-use crate::canvas::Canvas;
+use crate::canvas::{Canvas, CanvasError};
 use crate::canvas::canvas::Errorable;
 use crate::canvas::CanvasError::MachineError;
-use crate::Event;
+use crate::{Deadlock, Event, ExecutionFailed, HostFunctions, MessageNeverReceived};
+use crate::random::Random;
+use crate::sequencer::Schedule;
 
 impl Canvas {
     pub fn tick(&mut self, count: u16) -> Errorable {
         let ids: Vec<u16> = self.blocks.iter().map(|b| b.id).collect();
+        let mut failure = None;
 
         for _ in 0..count {
+            self.seq.ticks = self.seq.ticks.wrapping_add(1);
+
             // Collect the messages, and route them to their destination blocks.
             self.route_messages()?;
 
@@ -19,27 +24,57 @@ impl Canvas {
 
             // Tick the machine sequencer.
             if !self.seq.is_halted() {
-                self.seq
+                let result = self.seq
                     .step(self.machine_cycle_per_tick)
-                    .map_err(|cause| MachineError { cause: cause.clone() })?;
+                    .map_err(|cause| MachineError { cause });
+
+                defer_failure(result, &mut failure)?;
+
+                // Stop the machines that can never receive their messages.
+                let links = self.machine_links();
+                let result = self.seq.check_awaiting(&links).map_err(|cause| MachineError { cause });
+
+                defer_failure(result, &mut failure)?;
             }
         }
 
-        Ok(())
+        failure.map_or(Ok(()), Err)
     }
 
     /// Run every machine until all halts.
     pub fn run(&mut self) -> Errorable {
         self.seq.ready();
+        let mut failure = None;
 
         for _ in 1..1000 {
             if self.seq.is_halted() { break; }
-            self.tick(1)?;
+            defer_failure(self.tick(1), &mut failure)?;
         }
 
-        self.tick(1)?;
+        defer_failure(self.tick(1), &mut failure)?;
+
+        failure.map_or(Ok(()), Err)
+    }
+
+    /// Register the host functions that machines can invoke with `syscall`.
+    pub fn set_host<H: HostFunctions + 'static>(&mut self, host: H) {
+        self.seq.set_host(host);
+    }
 
-        Ok(())
+    /// Seed the random number generators of the canvas and its machines.
+    pub fn set_seed(&mut self, seed: u32) {
+        self.rng = Random::new(seed);
+        self.seq.set_seed(seed);
+    }
+
+    /// Set the clock speed and priority of the machine.
+    pub fn set_machine_schedule(&mut self, id: u16, schedule: Schedule) -> Errorable {
+        self.seq.set_schedule(id, schedule).map_err(|cause| MachineError { cause })
+    }
+
+    /// Supply text to the machine, to be read by `read_char` and `read_line`.
+    pub fn provide_input(&mut self, id: u16, text: &str) -> Errorable {
+        self.seq.provide_input(id, text).map_err(|cause| MachineError { cause })
     }
 
     /// Load the source program in Assembly to the machine.
@@ -57,4 +92,16 @@ impl Canvas {
 
         effects
     }
-}
\ No newline at end of file
+}
+/// Defer the failure of a machine, so the rest of the canvas keeps running.
+/// The failed machines are already stopped. Only the first failure is kept.
+fn defer_failure(result: Errorable, failure: &mut Option<CanvasError>) -> Errorable {
+    match result {
+        Err(error @ MachineError { cause: ExecutionFailed { .. } | Deadlock { .. } | MessageNeverReceived { .. } }) => {
+            failure.get_or_insert(error);
+            Ok(())
+        }
+
+        result => result,
+    }
+}
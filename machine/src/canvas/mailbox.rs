@@ -0,0 +1,201 @@
+use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+use crate::{Action, Message};
+
+/// In which order are the messages taken from the mailbox?
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum MailboxOrder {
+    /// Oldest message first.
+    #[default]
+    Fifo,
+
+    /// Newest message first.
+    Lifo,
+
+    /// Control messages such as resets and writes first, then oldest message first.
+    Priority,
+}
+
+/// What happens to a message that arrives at a full mailbox?
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum OverflowPolicy {
+    /// Drop the oldest message to make room for the new message.
+    #[default]
+    DropOldest,
+
+    /// Drop the new message.
+    DropNewest,
+
+    /// Hold the message in the sending machine, which is blocked until there is room.
+    /// Messages from other blocks are dropped, as they cannot be blocked.
+    BlockSender,
+}
+
+/// Result of delivering a message to a mailbox.
+#[derive(Debug, PartialEq)]
+pub enum Delivery {
+    Delivered,
+
+    /// The mailbox is full, so a message was dropped.
+    Dropped,
+
+    /// The mailbox is full, and the sender must hold on to the message.
+    Rejected(Message),
+}
+
+/// Incoming messages of a machine or a block.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Mailbox {
+    pub messages: VecDeque<Message>,
+    pub order: MailboxOrder,
+    pub overflow: OverflowPolicy,
+
+    /// How many messages were dropped because the mailbox is full?
+    pub dropped: u32,
+}
+
+impl Mailbox {
+    pub fn new() -> Mailbox {
+        Mailbox::default()
+    }
+
+    pub fn push(&mut self, message: Message) {
+        self.messages.push_back(message);
+    }
+
+    /// Take the next message, according to the mailbox order.
+    pub fn pop(&mut self) -> Option<Message> {
+        match self.order {
+            MailboxOrder::Fifo => self.messages.pop_front(),
+            MailboxOrder::Lifo => self.messages.pop_back(),
+
+            MailboxOrder::Priority => {
+                let index = self.messages.iter()
+                    .enumerate()
+                    .min_by_key(|(index, m)| (priority(&m.action), *index))?
+                    .0;
+
+                self.messages.remove(index)
+            }
+        }
+    }
+
+    /// Take every message, according to the mailbox order.
+    pub fn drain(&mut self) -> Vec<Message> {
+        let mut messages = Vec::with_capacity(self.messages.len());
+
+        while let Some(message) = self.pop() {
+            messages.push(message);
+        }
+
+        messages
+    }
+
+    /// Deliver the message, if the mailbox holds fewer messages than the limit.
+    /// Otherwise, apply the overflow policy. Only machines can be blocked.
+    pub fn deliver(&mut self, message: Message, limit: usize, can_block: bool) -> Delivery {
+        if self.messages.len() < limit {
+            self.push(message);
+            return Delivery::Delivered;
+        }
+
+        match self.overflow {
+            OverflowPolicy::DropOldest => {
+                self.push(message);
+                while self.messages.len() > limit { self.messages.pop_front(); }
+            }
+
+            OverflowPolicy::BlockSender if can_block => return Delivery::Rejected(message),
+            OverflowPolicy::DropNewest | OverflowPolicy::BlockSender => {}
+        }
+
+        self.dropped += 1;
+        Delivery::Dropped
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+}
+
+/// Lower values are taken first.
+fn priority(action: &Action) -> u8 {
+    match action {
+        Action::Reset => 0,
+        Action::Override { .. } | Action::Write { .. } => 1,
+        Action::Read { .. } => 2,
+        Action::Data { .. } | Action::Midi { .. } => 3,
+        Action::Ping => 4,
+    }
+}
+
+#[cfg(test)]
+mod mailbox_tests {
+    use super::*;
+    use crate::canvas::wire::port;
+
+    fn message(action: Action) -> Message {
+        Message { action, sender: port(0, 0), recipient: Some(1) }
+    }
+
+    fn data(v: u16) -> Message {
+        message(Action::Data { body: vec![v] })
+    }
+
+    fn mailbox(order: MailboxOrder, messages: Vec<Message>) -> Mailbox {
+        Mailbox { messages: messages.into(), order, ..Default::default() }
+    }
+
+    #[test]
+    fn test_fifo_and_lifo() {
+        let mut m = mailbox(MailboxOrder::Fifo, vec![data(1), data(2), data(3)]);
+        assert_eq!(m.drain(), [data(1), data(2), data(3)]);
+
+        let mut m = mailbox(MailboxOrder::Lifo, vec![data(1), data(2), data(3)]);
+        assert_eq!(m.drain(), [data(3), data(2), data(1)]);
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut m = mailbox(MailboxOrder::Priority, vec![
+            message(Action::Ping),
+            data(1),
+            message(Action::Reset),
+            data(2),
+        ]);
+
+        assert_eq!(m.drain(), [message(Action::Reset), data(1), data(2), message(Action::Ping)]);
+        assert!(m.is_empty());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let mut m = mailbox(MailboxOrder::Fifo, vec![data(1), data(2)]);
+        assert_eq!(m.deliver(data(3), 2, true), Delivery::Dropped);
+        assert_eq!(m.messages, [data(2), data(3)]);
+
+        m.overflow = OverflowPolicy::DropNewest;
+        assert_eq!(m.deliver(data(4), 2, true), Delivery::Dropped);
+        assert_eq!(m.messages, [data(2), data(3)]);
+
+        m.overflow = OverflowPolicy::BlockSender;
+        assert_eq!(m.deliver(data(5), 2, true), Delivery::Rejected(data(5)));
+        assert_eq!(m.deliver(data(5), 2, false), Delivery::Dropped);
+        assert_eq!(m.dropped, 3);
+
+        assert_eq!(m.deliver(data(6), 3, true), Delivery::Delivered);
+        assert_eq!(m.drain(), [data(2), data(3), data(6)]);
+    }
+}
@@ -5,6 +5,7 @@ pub mod vec_helper;
 pub mod event;
 pub mod message;
 pub mod virtual_io;
+pub mod mailbox;
 
 mod send_message;
 mod wiring;
@@ -14,3 +15,4 @@ mod execution;
 
 pub use canvas::Canvas;
 pub use canvas_error::*;
+pub use mailbox::{Delivery, Mailbox, MailboxOrder, OverflowPolicy};
@@ -9,15 +9,23 @@ impl Canvas {
         let mut messages = self.consume_messages();
         messages.extend(self.seq.consume_messages());
 
+        // Messages held back by machines, as the recipient's inbox is full.
+        let mut held: Vec<Message> = vec![];
+
         // If the message has a recipient, send it directly to the machine.
         // Otherwise, identify connected blocks and send the message to them.
         for message in messages {
-            match message.recipient {
-                Some(_) => self.send_message_to_recipient(message)?,
-                None => self.send_message_to_port(message)?,
+            // Keep the order of the messages from a blocked machine.
+            if held.iter().any(|m| m.sender.block == message.sender.block) {
+                held.push(message);
+                continue;
             }
+
+            held.extend(self.dispatch_message(message, true)?);
         }
 
+        self.seq.hold_messages(held);
+
         Ok(())
     }
 
@@ -1,31 +1,40 @@
-use crate::canvas::Canvas;
+use crate::canvas::{Canvas, CanvasError, Delivery};
 use crate::canvas::canvas::Errorable;
 use crate::canvas::CanvasError::{MissingMessageRecipient};
 use crate::{Action, Message};
-use crate::blocks::BlockData::Machine;
 use crate::canvas::wire::{port, Port};
 
 impl Canvas {
     /// Sends the message to the destination port.
     pub fn send_message_to_port(&mut self, message: Message) -> Errorable {
+        self.dispatch_message(message, false)?;
+        Ok(())
+    }
+
+    /// Sends the message to its recipient, or to the blocks connected to the sender port.
+    /// Returns the messages that the sending machine must hold on to,
+    /// as the recipient's mailbox is full and blocks the sender.
+    pub(crate) fn dispatch_message(&mut self, message: Message, can_block: bool) -> Result<Vec<Message>, CanvasError> {
         // If the message has a recipient, send it directly to the machine instead.
         if message.recipient.is_some() {
-            return self.send_message_to_recipient(message);
+            return Ok(self.deliver(message, None, can_block)?.into_iter().collect());
         }
 
-        // There might be more than one destination machine connected to a port.
-        let recipients = self.resolve_port(message.sender);
+        let mut held = vec![];
 
+        // There might be more than one destination machine connected to a port.
         // We submit different messages to each blocks.
-        for recipient_id in recipients {
-            self.send_message_to_recipient(Message {
+        for (wire_id, recipient_id) in self.resolve_port(message.sender) {
+            let message = Message {
                 action: message.action.clone(),
                 sender: message.sender,
                 recipient: Some(recipient_id),
-            })?;
+            };
+
+            held.extend(self.deliver(message, Some(wire_id), can_block)?);
         }
 
-        Ok(())
+        Ok(held)
     }
 
     /// Send a message from an actor to another actor.
@@ -41,7 +50,7 @@ impl Canvas {
 
     /// Sends the message to the specified block.
     pub fn send_message_to_block(&mut self, block_id: u16, action: Action) -> Errorable {
-        self.mut_block(block_id)?.inbox.push_back(Message {
+        self.mut_block(block_id)?.inbox.push(Message {
             sender: port(block_id, 60000),
             action,
             recipient: Some(block_id),
@@ -51,53 +60,54 @@ impl Canvas {
     }
 
     pub fn send_message_to_recipient(&mut self, message: Message) -> Errorable {
+        self.deliver(message, None, false)?;
+        Ok(())
+    }
+
+    /// Deliver the message to the recipient's mailbox, applying its overflow policy.
+    /// Returns the message if it is rejected, so the sender can try again later.
+    fn deliver(&mut self, message: Message, wire_id: Option<u16>, can_block: bool) -> Result<Option<Message>, CanvasError> {
         let inbox_limit = self.inbox_limit;
 
         let Some(recipient_id) = message.recipient else {
             return Err(MissingMessageRecipient { message });
         };
 
-        if let Ok(block) = self.mut_block(recipient_id) {
-            match block.data {
-                // Send the message directly to the machine.
-                Machine { machine_id } => {
-                    if let Some(m) = self.seq.get_mut(machine_id) {
-                        m.inbox.push_back(message);
-
-                        if m.inbox.len() > inbox_limit {
-                            m.inbox.pop_front();
-                        }
-                    }
-                }
+        // Only machines can hold on to their messages.
+        let can_block = can_block && self.seq.get(message.sender.block).is_some();
+
+        // Messages to a missing block or machine are ignored.
+        let Ok(inbox) = self.mailbox_mut(recipient_id) else { return Ok(None); };
 
-                _ => {
-                    block.inbox.push_back(message);
+        match inbox.deliver(message, inbox_limit, can_block) {
+            Delivery::Delivered => Ok(None),
+            Delivery::Rejected(message) => Ok(Some(message)),
 
-                    if block.inbox.len() > inbox_limit {
-                        block.inbox.pop_front();
-                    }
+            Delivery::Dropped => {
+                if let Some(id) = wire_id {
+                    *self.wire_dropped.entry(id).or_default() += 1;
                 }
+
+                Ok(None)
             }
         }
-
-        Ok(())
     }
 
     /// Given the sender's port, resolve the target block ids.
     /// TODO: improve bi-directional connection resolution.
-    fn resolve_port(&self, sender: Port) -> Vec<u16> {
-        let targets: Vec<u16> = self.wires.iter()
+    fn resolve_port(&self, sender: Port) -> Vec<(u16, u16)> {
+        let targets: Vec<(u16, u16)> = self.wires.iter()
             .filter(|w| w.source == sender)
-            .map(|w| w.target.block)
+            .map(|w| (w.id, w.target.block))
             .collect();
 
         if !targets.is_empty() {
             return targets;
         }
 
-        let sources: Vec<u16> = self.wires.iter()
+        let sources: Vec<(u16, u16)> = self.wires.iter()
             .filter(|w| w.target == sender)
-            .map(|w| w.source.block)
+            .map(|w| (w.id, w.source.block))
             .collect();
 
         if !sources.is_empty() {
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use snafu::ensure;
 use crate::canvas::{Canvas, CanvasError};
 use crate::canvas::canvas::Errorable;
@@ -40,10 +41,30 @@ impl Canvas {
             return Err(CannotFindWire { src, dst });
         };
 
-        self.wires.remove(wire_index);
+        let wire = self.wires.remove(wire_index);
+        self.wire_dropped.remove(&wire.id);
         Ok(())
     }
 
+    /// Blocks connected to each machine by a wire, in either direction.
+    /// These are the blocks that can send a message to the machine.
+    pub fn machine_links(&self) -> HashMap<u16, Vec<u16>> {
+        let mut links: HashMap<u16, Vec<u16>> = HashMap::new();
+
+        for wire in &self.wires {
+            let (a, b) = (wire.source.block, wire.target.block);
+
+            for (id, peer) in [(a, b), (b, a)] {
+                if self.seq.get(id).is_none() { continue; }
+
+                let peers = links.entry(id).or_default();
+                if !peers.contains(&peer) { peers.push(peer); }
+            }
+        }
+
+        links
+    }
+
     pub fn send_message_to_sinks(&mut self, id: u16, action: Action) -> Errorable {
         let wires: Vec<Wire> = self.wires.iter()
             .filter(|w| w.source.block == id)
@@ -1,16 +1,25 @@
 use std::fs;
+use std::io::{self, BufRead, Write};
 use crate::binary::bytes::{u16_vec_to_u8, u8_vec_to_u16};
-use crate::{Execute, Machine};
+use crate::{format_source, ControlFlowGraph, Event, Execute, Host, Machine, Parser};
+use crate::register::Register::PC;
+use crate::cli::native_host;
 use crate::cli::CLIError;
-use crate::cli::CLIError::{CannotParse, CannotReadFile, CannotWriteToFile, RunFailed};
-use crate::compile::compile_to_binary;
+use crate::cli::CLIError::{CannotParse, CannotReadFile, CannotWriteToFile, CycleLimitExceeded, NotFormatted, RunFailed};
+use crate::compile::compile_parser_to_binary;
 use crate::run::load_from_binary;
 
 type Errorable = Result<(), CLIError>;
 
-pub fn compile_to_file(src_path: &str, out_path: &str) -> Errorable {
+pub fn compile_to_file(src_path: &str, out_path: &str, optimize: bool) -> Errorable {
     let source = fs::read_to_string(&src_path).map_err(|_| CannotReadFile)?;
-    let bytecode = compile_to_binary(&source).map_err(|error| CannotParse { error })?;
+    warn_stack_usage(&source);
+
+    let mut parser = Parser::new(&source);
+    parser.optimize = optimize;
+    parser.parse().map_err(|error| CannotParse { error })?;
+
+    let bytecode = compile_parser_to_binary(parser);
 
     let bytes = u16_vec_to_u8(bytecode);
     fs::write(out_path, bytes).map_err(|_| CannotWriteToFile)?;
@@ -23,8 +32,9 @@ pub fn run_from_binary_file(path: &str, is_debug: bool) -> Errorable {
 
     let mut m = load_from_binary(&u8_vec_to_u16(bytes))?;
     m.is_debug = is_debug;
+    m.host = Host::new(native_host());
 
-    m.run().map_err(|error| RunFailed { error })?;
+    run_with_io(&mut m, io::stdin().lock(), io::stdout())?;
 
     if is_debug {
         println!("stack: {:?}", m.mem.read_stack(10));
@@ -35,13 +45,95 @@ pub fn run_from_binary_file(path: &str, is_debug: bool) -> Errorable {
 
 pub fn run_from_source(path: &str, is_debug: bool) -> Errorable {
     let source = fs::read_to_string(path).map_err(|_| CannotReadFile)?;
+    warn_stack_usage(&source);
 
     let m: Result<Machine, _> = (*source).try_into();
     let mut m = m.map_err(|error| CannotParse { error })?;
     m.is_debug = is_debug;
+    m.host = Host::new(native_host());
+
+    run_with_io(&mut m, io::stdin().lock(), io::stdout())
+}
+
+/// Print the control-flow graph or the call graph of the source as DOT, or both as JSON.
+pub fn print_graph(src_path: &str, calls: bool, json: bool) -> Errorable {
+    let source = fs::read_to_string(src_path).map_err(|_| CannotReadFile)?;
+    let parser: Parser = (*source).try_into().map_err(|error| CannotParse { error })?;
+    let graph = ControlFlowGraph::new(&parser);
+
+    let text = match (calls, json) {
+        (_, true) => serde_json::to_string_pretty(&graph).map_err(|_| CannotWriteToFile)?,
+        (true, _) => graph.to_call_graph_dot(),
+        _ => graph.to_dot(),
+    };
+
+    print!("{text}");
+    Ok(())
+}
+
+/// Print the formatted source, write it back to the file, or check that the file is already formatted.
+pub fn format_file(path: &str, write: bool, check: bool) -> Errorable {
+    let source = fs::read_to_string(path).map_err(|_| CannotReadFile)?;
+    let formatted = format_source(&source);
+
+    if check {
+        return if formatted == source { Ok(()) } else { Err(NotFormatted { path: path.into() }) };
+    }
+
+    if write {
+        return fs::write(path, formatted).map_err(|_| CannotWriteToFile);
+    }
+
+    print!("{formatted}");
+    Ok(())
+}
+
+/// Print the stack warnings of the program. They do not prevent it from compiling.
+fn warn_stack_usage(source: &str) {
+    let Ok(parser) = Parser::try_from(source) else { return; };
+
+    for warning in parser.verify() {
+        eprintln!("warning: {warning}");
+    }
+}
+
+/// Run the machine until it halts, writing the printed text to the output.
+/// Reads a line from the input whenever the machine waits for input.
+pub fn run_with_io<R: BufRead, W: Write>(m: &mut Machine, input: R, output: W) -> Errorable {
+    run_with_io_limit(m, input, output, None)
+}
+
+/// Run the machine like `run_with_io`, but fail if it does not halt within the number of cycles.
+pub fn run_with_io_limit<R: BufRead, W: Write>(m: &mut Machine, mut input: R, mut output: W, max_cycles: Option<u64>) -> Errorable {
+    m.reg.set(PC, 0);
+    let mut cycles: u64 = 0;
+
+    while !m.should_halt() {
+        if max_cycles.is_some_and(|max| cycles >= max) {
+            return Err(CycleLimitExceeded { cycles });
+        }
+
+        m.tick().map_err(|error| RunFailed { error })?;
+        cycles += 1;
+
+        for event in m.events.drain(..) {
+            if let Event::Print { text } = event {
+                writeln!(output, "{text}").map_err(|_| CannotWriteToFile)?;
+            }
+        }
+
+        if m.awaiting_input {
+            output.flush().map_err(|_| CannotWriteToFile)?;
+            let mut line = String::new();
+
+            // The input is closed at the end of the stream.
+            match input.read_line(&mut line) {
+                Ok(0) | Err(_) => m.close_input(),
+                Ok(_) => m.provide_input(&line),
+            }
+        }
+    }
 
-    m.run().map_err(|error| RunFailed { error })?;
-   
     Ok(())
 }
 
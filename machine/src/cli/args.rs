@@ -17,6 +17,10 @@ pub enum Commands {
 
         /// Path to the output bytecode.
         out: String,
+
+        /// Optimize the operations before compiling.
+        #[arg(short, long)]
+        optimize: bool,
     },
 
     /// Run the bytecode or text assembly format.
@@ -32,4 +36,38 @@ pub enum Commands {
         #[arg(short, long)]
         debug: bool,
     },
+
+    /// Print the control-flow graph of the assembly source in the Graphviz DOT format.
+    Graph {
+        /// Path to the assembly source code.
+        src: String,
+
+        /// Print the call graph instead.
+        #[arg(short, long)]
+        calls: bool,
+
+        /// Print both graphs as JSON instead.
+        #[arg(short, long, conflicts_with = "calls")]
+        json: bool,
+    },
+
+    /// Format the assembly source, and print it.
+    Fmt {
+        /// Path to the assembly source code.
+        path: String,
+
+        /// Overwrite the file with the formatted source instead.
+        #[arg(short, long)]
+        write: bool,
+
+        /// Fail if the file is not formatted, without changing it.
+        #[arg(short, long, conflicts_with = "write")]
+        check: bool,
+    },
+
+    /// Run every assembly file in the directory, and check its assertions and expectations.
+    Test {
+        /// Path to the directory of assembly files.
+        dir: String,
+    },
 }
@@ -24,4 +24,13 @@ pub enum CLIError {
 
     #[snafu(display(""))]
     RunFailed { error: RuntimeError },
+
+    #[snafu(display(""))]
+    TestsFailed { failed: usize, total: usize },
+
+    #[snafu(display(""))]
+    CycleLimitExceeded { cycles: u64 },
+
+    #[snafu(display(""))]
+    NotFormatted { path: String },
 }
@@ -0,0 +1,27 @@
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use crate::HostRegistry;
+
+/// Push the milliseconds elapsed since the program started, wrapping around at 0xFFFF.
+pub const SYSCALL_UPTIME_MS: u16 = 0;
+
+/// Push the unix time in seconds as two values: the high word, then the low word.
+pub const SYSCALL_UNIX_TIME: u16 = 1;
+
+/// Host functions available to programs running in the command line.
+pub fn native_host() -> HostRegistry {
+    let mut host = HostRegistry::new();
+    let started_at = Instant::now();
+
+    host.register(SYSCALL_UPTIME_MS, move |s| {
+        s.push(started_at.elapsed().as_millis() as u16)
+    });
+
+    host.register(SYSCALL_UNIX_TIME, |s| {
+        let secs = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+
+        s.push((secs >> 16) as u16)?;
+        s.push(secs as u16)
+    });
+
+    host
+}
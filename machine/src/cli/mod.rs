@@ -1,7 +1,11 @@
 pub mod args;
 pub mod actions;
 pub mod cli_error;
+pub mod host;
+pub mod test_runner;
 
 pub use args::*;
 pub use actions::*;
 pub use cli_error::CLIError;
+pub use host::native_host;
+pub use test_runner::run_tests;
@@ -0,0 +1,146 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::{Host, Machine, RuntimeError};
+use crate::cli::{native_host, run_with_io_limit, CLIError};
+use crate::cli::CLIError::{CannotReadFile, CycleLimitExceeded, RunFailed, TestsFailed};
+
+/// How many instructions a test program may run before it is considered stuck.
+pub const MAX_TEST_CYCLES: u64 = 1_000_000;
+
+/// Expected results of a test program, written as comments in its source.
+///
+/// `; expect-stack: 1 2 0xFF` lists the values left on the stack, from the bottom.
+/// `; expect-output: Hello` is a line the program prints. Lines are expected in order.
+/// `; test-skip: needs a canvas` leaves the program out of the test run, e.g. when it needs a peripheral.
+#[derive(Debug, Default, PartialEq)]
+pub struct Expectations {
+    pub stack: Option<Vec<u16>>,
+    pub output: Option<Vec<String>>,
+    pub skip: Option<String>,
+}
+
+impl Expectations {
+    pub fn from_source(source: &str) -> Result<Expectations, String> {
+        let mut expect = Expectations::default();
+
+        for line in source.lines() {
+            let Some(comment) = line.trim().strip_prefix(';') else { continue; };
+            let comment = comment.trim();
+
+            if let Some(values) = comment.strip_prefix("expect-stack:") {
+                let values = values.split_whitespace().map(parse_value).collect::<Result<Vec<_>, _>>()?;
+                expect.stack.get_or_insert_with(Vec::new).extend(values);
+            }
+
+            if let Some(text) = comment.strip_prefix("expect-output:") {
+                expect.output.get_or_insert_with(Vec::new).push(text.trim().to_string());
+            }
+
+            if let Some(reason) = comment.strip_prefix("test-skip:") {
+                expect.skip = Some(reason.trim().to_string());
+            }
+        }
+
+        Ok(expect)
+    }
+}
+
+fn parse_value(text: &str) -> Result<u16, String> {
+    let value = if let Some(hex) = text.strip_prefix("0x") {
+        u16::from_str_radix(hex, 16)
+    } else if let Some(binary) = text.strip_prefix("0b") {
+        u16::from_str_radix(binary, 2)
+    } else {
+        text.parse()
+    };
+
+    value.map_err(|_| format!("'{text}' is not a valid stack value"))
+}
+
+/// Run the program and check its assertions and expectations.
+/// Returns the reason of the failure.
+pub fn test_source(source: &str) -> Result<(), String> {
+    let expect = Expectations::from_source(source)?;
+
+    let m: Result<Machine, _> = source.try_into();
+    let mut m = m.map_err(|error| format!("cannot parse: {error}"))?;
+    m.host = Host::new(native_host());
+
+    // Programs that read input get an empty input.
+    let mut output = vec![];
+
+    match run_with_io_limit(&mut m, io::empty(), &mut output, Some(MAX_TEST_CYCLES)) {
+        Ok(()) => {}
+        Err(RunFailed { error: error @ RuntimeError::AssertionFailed { .. } }) => return Err(error.to_string()),
+        Err(RunFailed { error }) => return Err(format!("runtime error: {error}")),
+        Err(CycleLimitExceeded { cycles }) => return Err(format!("timed out after {cycles} cycles")),
+        Err(error) => return Err(format!("{error:?}")),
+    }
+
+    if let Some(expected) = expect.stack {
+        let len = m.stack().len();
+        let stack = m.mem.read_stack(len);
+
+        if stack != expected {
+            return Err(format!("expected stack {expected:?}, got {stack:?}"));
+        }
+    }
+
+    if let Some(expected) = expect.output {
+        let output = String::from_utf8_lossy(&output);
+        let lines: Vec<&str> = output.lines().collect();
+
+        if lines != expected {
+            return Err(format!("expected output {expected:?}, got {lines:?}"));
+        }
+    }
+
+    Ok(())
+}
+
+/// Is the path an assembly source file?
+pub fn is_assembly(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "asm")
+}
+
+/// Test every `.asm` file in the directory, and report the result of each file.
+pub fn run_tests(dir: &str) -> Result<(), CLIError> {
+    let mut paths: Vec<_> = fs::read_dir(dir).map_err(|_| CannotReadFile)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| is_assembly(path))
+        .collect();
+
+    paths.sort();
+
+    let mut failed = 0;
+    let mut skipped = 0;
+
+    for path in &paths {
+        let name = path.file_name().map_or_else(|| path.display().to_string(), |n| n.to_string_lossy().to_string());
+        let source = fs::read_to_string(path).map_err(|_| CannotReadFile)?;
+
+        if let Ok(Expectations { skip: Some(reason), .. }) = Expectations::from_source(&source) {
+            skipped += 1;
+            println!("SKIP {name}: {reason}");
+            continue;
+        }
+
+        match test_source(&source) {
+            Ok(()) => println!("PASS {name}"),
+
+            Err(reason) => {
+                failed += 1;
+                println!("FAIL {name}: {reason}");
+            }
+        }
+    }
+
+    println!("\n{} passed, {} failed, {} skipped", paths.len() - failed - skipped, failed, skipped);
+
+    if failed > 0 {
+        return Err(TestsFailed { failed, total: paths.len() });
+    }
+
+    Ok(())
+}
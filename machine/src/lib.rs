@@ -11,6 +11,7 @@ pub mod canvas;
 pub mod rewind;
 pub mod audio;
 pub mod blocks;
+pub mod random;
 
 pub use op::*;
 pub use machine::*;
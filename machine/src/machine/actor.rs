@@ -1,4 +1,4 @@
-use crate::{Action, Machine, MEMORY_SIZE, Message, RuntimeError};
+use crate::{Action, Machine, Message, RuntimeError};
 use crate::canvas::wire::port;
 
 type Errorable = Result<(), RuntimeError>;
@@ -32,8 +32,9 @@ impl Actor for Machine {
 
             // A new message arrived!
             // We can process them now.
-            let Some(message) = self.inbox.pop_back() else { break; };
+            let Some(message) = self.inbox.pop() else { break; };
             self.expected_receives -= 1;
+            self.last_receive_tick = self.ticks;
 
             match message.action {
                 Action::Data { body } => {
@@ -43,22 +44,14 @@ impl Actor for Machine {
                 }
 
                 Action::Write { address, data } => {
-                    // Check if the data is within the bounds of the memory.
-                    let last_address = address as usize + data.len();
-                    if last_address >= MEMORY_SIZE as usize { continue; }
-
-                    for (i, byte) in data.iter().enumerate() {
-                        self.mem.set(address + i as u16, *byte);
-                    }
+                    // Ignore writes to the protected memory.
+                    let _ = self.mem.store(address, &data);
                 }
 
                 Action::Read { address, count } => {
                     if let Some(id) = self.id {
-                        let mut body = vec![];
-
-                        for i in 0..count {
-                            body.push(self.mem.get(address + i));
-                        }
+                        // Reading from the protected memory yields an empty body.
+                        let body = self.mem.load(address, count).unwrap_or_default();
 
                         self.outbox.push(Message {
                             action: Action::Data { body },
@@ -29,6 +29,14 @@ impl Decode for Machine {
     /// Returns the current instruction.
     /// Fetch and decode the opcode and its arguments into instruction.
     fn decode(&mut self) -> Op {
+        let pc = self.reg.get(PC);
+
+        // Use the pre-decoded instruction, and skip over its arguments.
+        if let Some(op) = self.mem.decoded(pc) {
+            self.reg.set(PC, pc + op.arity() as u16);
+            return op;
+        }
+
         // Fetch the opcode and decode it.
         let op: Op = self.opcode().into();
 
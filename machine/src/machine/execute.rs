@@ -4,11 +4,12 @@ use crate::{Event, RuntimeError};
 use crate::machine::{Decode, Machine};
 use crate::register::Register::PC;
 use crate::op::Op;
-use crate::mem::WithStringManager;
+use crate::mem::{Access, WithStringManager};
+use crate::machine::format::{format_value, parse_template, Format, Piece};
 use crate::machine::{Action, Actor};
 use crate::machine::virtual_mem::VirtualMemory;
-use crate::runtime_error::{IndexOutOfBoundsSnafu, NotEnoughValuesSnafu};
-use crate::RuntimeError::{CallStackExceeded, CannotDivideByZero, CannotLoadFromMemory, IntegerOverflow, IntegerUnderflow, MissingMessageBody, MissingReturnAddress, MissingValueToStore};
+use crate::runtime_error::{AssertionFailedSnafu, IndexOutOfBoundsSnafu, NotEnoughValuesSnafu};
+use crate::RuntimeError::{CallStackExceeded, CannotDivideByZero, CannotLoadFromMemory, IntegerOverflow, IntegerUnderflow, MissingMessageBody, MissingReturnAddress, MissingValueToStore, PcOutOfBounds, SegmentationFault};
 
 type Errorable = Result<(), RuntimeError>;
 
@@ -24,6 +25,10 @@ pub trait Execute {
 
     /// Returns whether the machine should halt.
     fn should_halt(&self) -> bool;
+
+    /// Invoke the error handler with the error code on the stack.
+    /// Returns the error if the machine cannot handle it.
+    fn trap(&mut self, error: RuntimeError) -> Errorable;
 }
 
 impl Execute for Machine {
@@ -48,8 +53,9 @@ impl Execute for Machine {
 
             Op::Load(addr) => {
                 if !self.read_virtual(addr, 1) {
-                    let v = self.mem.get(addr);
-                    self.stack().push(v).map_err(|_| CannotLoadFromMemory)?;
+                    for v in self.mem.load(addr, 1)? {
+                        self.stack().push(v).map_err(|_| CannotLoadFromMemory)?;
+                    }
                 }
             }
 
@@ -57,7 +63,7 @@ impl Execute for Machine {
                 let value = s.pop().map_err(|_| MissingValueToStore)?;
 
                 if !self.write_virtual(addr, vec![value]) {
-                    self.mem.set(addr, value);
+                    self.mem.store(addr, &[value])?;
                 }
             }
 
@@ -74,7 +80,7 @@ impl Execute for Machine {
                 }
 
                 if !self.write_virtual(address, body.clone()) {
-                    self.mem.write(address, &body);
+                    self.mem.store(address, &body)?;
                 }
             }
 
@@ -82,7 +88,7 @@ impl Execute for Machine {
                 let address = s.pop().map_err(|_| MissingValueToStore)?;
 
                 if !self.read_virtual(address, size) {
-                    for v in self.mem.read(address, size) {
+                    for v in self.mem.load(address, size)? {
                         self.stack().push(v)?;
                     }
                 }
@@ -93,7 +99,7 @@ impl Execute for Machine {
             Op::Sub => s.apply_two(|a, b| a.checked_sub(b).ok_or(IntegerUnderflow))?,
             Op::Mul => s.apply_two(|a, b| a.checked_mul(b).ok_or(IntegerOverflow))?,
             Op::Div => s.apply_two(|a, b| a.checked_div(b).ok_or(CannotDivideByZero))?,
-            Op::Mod => s.apply_two(|a, b| Ok(a % b))?,
+            Op::Mod => s.apply_two(|a, b| a.checked_rem(b).ok_or(CannotDivideByZero))?,
 
             // Increment and decrement.
             Op::Inc => s.apply(|v| v.checked_add(1).ok_or(IntegerOverflow))?,
@@ -202,6 +208,7 @@ impl Execute for Machine {
             }
 
             Op::LoadString(addr) => {
+                self.mem.check_access(addr, 1, Access::Read)?;
                 let text = self.mem.string().get_str_bytes(addr);
 
                 for v in text.iter() {
@@ -209,6 +216,126 @@ impl Execute for Machine {
                 }
             }
 
+            Op::PrintInt => {
+                let v = s.pop()?;
+                self.print_value(Format::Int, v)?;
+            }
+
+            Op::PrintHex => {
+                let v = s.pop()?;
+                self.print_value(Format::Hex, v)?;
+            }
+
+            Op::PrintChar => {
+                let v = s.pop()?;
+                self.print_value(Format::Char, v)?;
+            }
+
+            Op::PrintFmt(addr) => {
+                self.mem.check_access(addr, 1, Access::Read)?;
+
+                let bytes = self.mem.string().get_str_bytes(addr);
+                let template = self.mem.string().get_str_from_bytes(bytes)?;
+                let pieces = parse_template(&template);
+
+                // Every placeholder takes a value from the stack.
+                let count = pieces.iter().filter(|p| matches!(p, Piece::Value(_))).count() as u16;
+                let len = self.stack().len();
+                ensure!(len >= count, NotEnoughValuesSnafu { min: count, len });
+
+                let mut values = vec![];
+
+                for _ in 0..count {
+                    values.push(self.stack().pop()?);
+                }
+
+                // The first placeholder takes the deepest value.
+                values.reverse();
+                let mut values = values.into_iter();
+                let mut text = String::new();
+
+                for piece in pieces {
+                    match piece {
+                        Piece::Text(t) => text.push_str(&t),
+                        Piece::Value(format) => {
+                            let value = values.next().unwrap_or_default();
+                            text.push_str(&format_value(format, value, &mut self.mem)?);
+                        }
+                    }
+                }
+
+                self.events.push(Event::Print { text })
+            }
+
+            Op::ReadChar => {
+                match self.input.pop_front() {
+                    Some(v) => self.stack().push(v)?,
+                    None if self.input_closed => self.stack().push(0)?,
+                    None => jump = Some(self.wait_for_input(op)),
+                }
+            }
+
+            Op::ReadLine(addr) => {
+                let newline = self.input.iter().position(|&c| c == '\n' as u16);
+
+                if newline.is_none() && !self.input_closed {
+                    jump = Some(self.wait_for_input(op));
+                } else {
+                    let end = newline.unwrap_or(self.input.len());
+
+                    // Keep the input if the line cannot be stored.
+                    let Ok(size) = u16::try_from(end + 1) else {
+                        return Err(SegmentationFault { addr: u16::MAX, access: Access::Write });
+                    };
+                    self.mem.check_access(addr, size, Access::Write)?;
+
+                    let mut line: Vec<u16> = self.input.drain(..end).collect();
+                    if newline.is_some() { self.input.pop_front(); }
+
+                    // Lines from Windows hosts end with a carriage return.
+                    if line.last() == Some(&('\r' as u16)) { line.pop(); }
+
+                    let len = line.len() as u16;
+                    line.push(0);
+
+                    self.mem.store(addr, &line)?;
+                    self.stack().push(len)?;
+                }
+            }
+
+            Op::Assert => {
+                let actual = s.pop()?;
+                let pc = self.reg.get(PC);
+
+                ensure!(actual != 0, AssertionFailedSnafu { pc, expected: 1u16, actual });
+            }
+
+            Op::AssertEq => {
+                let expected = s.pop()?;
+                let actual = s.pop()?;
+                let pc = self.reg.get(PC);
+
+                ensure!(actual == expected, AssertionFailedSnafu { pc, expected, actual });
+            }
+
+            Op::StrLen(addr) => {
+                self.mem.check_access(addr, 1, Access::Read)?;
+                let len = self.mem.string().get_str_bytes(addr).len() as u16;
+
+                self.stack().push(len)?;
+            }
+
+            Op::CharAt(addr) => {
+                let index = s.pop()?;
+
+                self.mem.check_access(addr, 1, Access::Read)?;
+                let text = self.mem.string().get_str_bytes(addr);
+                let len = text.len() as u16;
+
+                ensure!(index < len, IndexOutOfBoundsSnafu { len, index });
+                self.stack().push(text[index as usize])?;
+            }
+
             Op::Call(address) => {
                 let pc = self.reg.get(PC);
                 self.call_stack().push(pc).map_err(|_| CallStackExceeded)?;
@@ -218,7 +345,7 @@ impl Execute for Machine {
 
             Op::Return => {
                 let address = self.call_stack().pop().map_err(|_| MissingReturnAddress)?;
-                jump = Some(address + 1)
+                jump = Some(address.wrapping_add(1))
             }
 
             Op::Send(port, size) => {
@@ -241,8 +368,8 @@ impl Execute for Machine {
             Op::Or => s.apply_two(|a, b| Ok(a | b))?,
             Op::Xor => s.apply_two(|a, b| Ok(a ^ b))?,
             Op::Not => s.apply(|a| Ok(a.not()))?,
-            Op::LeftShift => s.apply_two(|a, b| Ok(a << b))?,
-            Op::RightShift => s.apply_two(|a, b| Ok(a >> b))?,
+            Op::LeftShift => s.apply_two(|a, b| a.checked_shl(b.into()).ok_or(IntegerOverflow))?,
+            Op::RightShift => s.apply_two(|a, b| a.checked_shr(b.into()).ok_or(IntegerOverflow))?,
 
             // Pause the execution of the thread
             Op::SleepTick(tick) => {
@@ -254,6 +381,44 @@ impl Execute for Machine {
                 self.sleeping = true;
                 self.events.push(Event::Sleep {ms})
             },
+
+            Op::Syscall(id) => {
+                let host = self.host.clone();
+                host.call(id, &mut self.stack())?;
+            }
+
+            Op::Rand => {
+                let v = self.rng.next_u16();
+                self.stack().push(v)?;
+            }
+
+            Op::RandRange => {
+                let max = s.pop()?;
+                let min = s.pop()?;
+
+                let v = self.rng.range(min, max);
+                self.stack().push(v)?;
+            }
+
+            Op::Ticks => {
+                let v = self.ticks;
+                self.stack().push(v)?;
+            }
+
+            Op::Cycles => {
+                let v = self.cycles;
+                self.stack().push(v)?;
+            }
+
+            Op::TicksSinceReceive => {
+                let v = self.ticks.wrapping_sub(self.last_receive_tick);
+                self.stack().push(v)?;
+            }
+
+            Op::SelectBank(port) => {
+                let bank = s.pop()?;
+                self.select_bank(port, bank)?;
+            }
         };
 
         // Advance or jump the program counter.
@@ -268,9 +433,19 @@ impl Execute for Machine {
 
     // Fetch, decode and execute the instruction.
     fn tick(&mut self) -> Errorable {
+        // Only the code segment is executable.
+        let pc = self.reg.get(PC);
+
+        // The faulty instruction cannot run, so there is nothing for the error handler to resume after.
+        if self.mem.check_access(pc, 1, Access::Execute).is_err() {
+            return Err(PcOutOfBounds { pc });
+        }
+
         let op = self.decode();
+        self.exec_op(op).or_else(|error| self.trap(error))?;
 
-        self.exec_op(op)
+        self.cycles = self.cycles.wrapping_add(1);
+        Ok(())
     }
 
     fn run(&mut self) -> Errorable {
@@ -278,6 +453,9 @@ impl Execute for Machine {
 
         while !self.should_halt() {
             self.tick()?;
+
+            // Nobody can supply the input, so reads stop waiting for it.
+            if self.awaiting_input { self.close_input(); }
         }
 
         Ok(())
@@ -288,4 +466,33 @@ impl Execute for Machine {
 
         op == Op::Halt || op == Op::Eof
     }
-}
\ No newline at end of file
+
+    fn trap(&mut self, error: RuntimeError) -> Errorable {
+        let Some(handler) = self.error_handler else { return Err(error); };
+
+        // The handler is invoked like a subroutine.
+        // Returning from the handler resumes execution after the faulty instruction.
+        let pc = self.reg.get(PC);
+        if self.call_stack().push(pc).is_err() { return Err(error); }
+        if self.stack().push(error.code()).is_err() { return Err(error); }
+
+        self.reg.set(PC, handler);
+        Ok(())
+    }
+}
+impl Machine {
+    /// Wait for the host to supply input.
+    /// Returns the address of the instruction, so it runs again once the input arrives.
+    fn wait_for_input(&mut self, op: Op) -> u16 {
+        self.awaiting_input = true;
+        self.reg.get(PC) - op.arity() as u16
+    }
+
+    /// Print a single value in the given format.
+    fn print_value(&mut self, format: Format, value: u16) -> Errorable {
+        let text = format_value(format, value, &mut self.mem)?;
+        self.events.push(Event::Print { text });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+use crate::mem::{Access, Memory, WithStringManager};
+use crate::RuntimeError;
+use crate::RuntimeError::CannotReadStringFromBytes;
+
+/// How a value from the stack is printed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// Unsigned decimal, `%d`.
+    Int,
+
+    /// Lowercase hexadecimal, `%x`.
+    Hex,
+
+    /// UTF-16 code unit, `%c`.
+    Char,
+
+    /// Address of a null-terminated string, `%s`.
+    Str,
+}
+
+/// Part of a `print_fmt` template.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Piece {
+    Text(String),
+    Value(Format),
+}
+
+/// Split the template into text and placeholders.
+/// `%%` prints a percent sign. Unknown placeholders are printed as they are.
+pub fn parse_template(template: &str) -> Vec<Piece> {
+    let mut pieces = vec![];
+    let mut text = String::new();
+    let mut chars = template.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            text.push(c);
+            continue;
+        }
+
+        let format = match chars.peek() {
+            Some('d') => Format::Int,
+            Some('x') => Format::Hex,
+            Some('c') => Format::Char,
+            Some('s') => Format::Str,
+
+            Some('%') => {
+                chars.next();
+                text.push('%');
+                continue;
+            }
+
+            _ => {
+                text.push('%');
+                continue;
+            }
+        };
+
+        chars.next();
+
+        if !text.is_empty() {
+            pieces.push(Piece::Text(std::mem::take(&mut text)));
+        }
+
+        pieces.push(Piece::Value(format));
+    }
+
+    if !text.is_empty() {
+        pieces.push(Piece::Text(text));
+    }
+
+    pieces
+}
+
+/// Print a single value in the given format.
+/// Strings are read from memory, so the address must be readable.
+pub fn format_value(format: Format, value: u16, mem: &mut Memory) -> Result<String, RuntimeError> {
+    match format {
+        Format::Int => Ok(value.to_string()),
+        Format::Hex => Ok(format!("{value:x}")),
+        Format::Char => char::from_u32(value as u32).map(String::from).ok_or(CannotReadStringFromBytes),
+
+        Format::Str => {
+            mem.check_access(value, 1, Access::Read)?;
+
+            let bytes = mem.string().get_str_bytes(value);
+            mem.string().get_str_from_bytes(bytes)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::Format::*;
+
+    #[test]
+    fn test_parse_template() {
+        assert_eq!(parse_template("x=%d, y=%x%c 100%% %q %s"), [
+            Piece::Text("x=".into()),
+            Piece::Value(Int),
+            Piece::Text(", y=".into()),
+            Piece::Value(Hex),
+            Piece::Value(Char),
+            Piece::Text(" 100% %q ".into()),
+            Piece::Value(Str),
+        ]);
+
+        assert_eq!(parse_template("%"), [Piece::Text("%".into())]);
+    }
+
+    #[test]
+    fn test_format_value() {
+        let mut mem = Memory::new();
+        let addr = mem.string().add_str("hi");
+
+        assert_eq!(format_value(Int, 65535, &mut mem), Ok("65535".into()));
+        assert_eq!(format_value(Hex, 0xBEEF, &mut mem), Ok("beef".into()));
+        assert_eq!(format_value(Char, 'é' as u16, &mut mem), Ok("é".into()));
+        assert_eq!(format_value(Char, 0xD83C, &mut mem), Err(CannotReadStringFromBytes));
+        assert_eq!(format_value(Str, addr, &mut mem), Ok("hi".into()));
+    }
+}
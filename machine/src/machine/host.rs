@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::sync::{Arc, Mutex};
+use crate::mem::StackManager;
+use crate::RuntimeError;
+use crate::RuntimeError::MissingHostFunction;
+
+type HostResult = Result<(), RuntimeError>;
+
+/// Services provided by the host device, invoked by the `syscall` instruction.
+/// Host functions read their arguments from the stack and push their results back onto it.
+pub trait HostFunctions: Send {
+    /// Invoke the host function with the given syscall number.
+    fn call(&mut self, id: u16, stack: &mut StackManager) -> HostResult;
+}
+
+type HostFunction = Box<dyn FnMut(&mut StackManager) -> HostResult + Send>;
+
+/// Registry of host functions, keyed by their syscall number.
+#[derive(Default)]
+pub struct HostRegistry {
+    functions: HashMap<u16, HostFunction>,
+}
+
+impl HostRegistry {
+    pub fn new() -> HostRegistry {
+        HostRegistry { functions: HashMap::new() }
+    }
+
+    /// Register a host function under the given syscall number.
+    /// Replaces the existing function with the same number.
+    pub fn register<F>(&mut self, id: u16, f: F)
+        where F: FnMut(&mut StackManager) -> HostResult + Send + 'static {
+        self.functions.insert(id, Box::new(f));
+    }
+
+    /// Is there a host function registered under the given syscall number?
+    pub fn has(&self, id: u16) -> bool {
+        self.functions.contains_key(&id)
+    }
+}
+
+impl HostFunctions for HostRegistry {
+    fn call(&mut self, id: u16, stack: &mut StackManager) -> HostResult {
+        let f = self.functions.get_mut(&id).ok_or(MissingHostFunction { id })?;
+        f(stack)
+    }
+}
+
+/// Shared handle to the host functions.
+/// The host lives outside the machine, so it is never serialized.
+#[derive(Clone, Default)]
+pub struct Host(Option<Arc<Mutex<dyn HostFunctions>>>);
+
+impl Host {
+    pub fn new<H: HostFunctions + 'static>(host: H) -> Host {
+        Host(Some(Arc::new(Mutex::new(host))))
+    }
+
+    /// Invoke the host function with the given syscall number.
+    pub fn call(&self, id: u16, stack: &mut StackManager) -> HostResult {
+        let Some(host) = &self.0 else { return Err(MissingHostFunction { id }); };
+        let mut host = host.lock().map_err(|_| MissingHostFunction { id })?;
+
+        host.call(id, stack)
+    }
+
+    pub fn is_attached(&self) -> bool {
+        self.0.is_some()
+    }
+}
+
+impl Debug for Host {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Host({})", if self.is_attached() { "attached" } else { "none" })
+    }
+}
+
+/// Machines are equal if they share the same host, or both have none.
+impl PartialEq for Host {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.0, &other.0) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod host_tests {
+    use super::*;
+    use crate::Machine;
+
+    #[test]
+    fn test_registry_call() -> HostResult {
+        let mut registry = HostRegistry::new();
+        registry.register(1, |s| s.push(42));
+
+        let mut m = Machine::new();
+        registry.call(1, &mut m.stack())?;
+        assert_eq!(m.stack().peek(), 42);
+
+        assert_eq!(registry.call(2, &mut m.stack()), Err(MissingHostFunction { id: 2 }));
+
+        Ok(())
+    }
+}
@@ -2,19 +2,24 @@ pub mod actor;
 pub mod decode;
 pub mod execute;
 pub mod runtime_error;
+pub mod host;
+pub mod format;
 mod virtual_mem;
 
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use serde::{Deserialize, Serialize};
-use crate::mem::{Memory, StackManager};
-use crate::{CALL_STACK_END, CALL_STACK_START, Op, ParseError, Parser, Register::FP, Registers};
+use crate::mem::{Memory, MemoryLayout, StackManager};
+use crate::random::Random;
+use crate::{Op, ParseError, Parser, Register::FP, Registers};
 
 pub use self::actor::Actor;
 pub use self::decode::Decode;
 pub use crate::canvas::event::Event;
 pub use self::execute::Execute;
 pub use crate::canvas::message::{Action, Message};
+use crate::canvas::Mailbox;
 pub use self::runtime_error::RuntimeError;
+pub use self::host::{Host, HostFunctions, HostRegistry};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Machine {
@@ -31,7 +36,7 @@ pub struct Machine {
     pub events: Vec<Event>,
 
     /// Inbox contains messages sent to this machine.
-    pub inbox: VecDeque<Message>,
+    pub inbox: Mailbox,
 
     /// Outbox contains messages sent from this machine.
     pub outbox: Vec<Message>,
@@ -47,19 +52,60 @@ pub struct Machine {
 
     /// How many tick remains until we resume execution?
     pub remaining_sleep_ticks: u16,
+
+    /// Address of the error handler, defined by `.on_error`.
+    /// Runtime errors jump to the handler instead of stopping the machine.
+    pub error_handler: Option<u16>,
+
+    /// Current tick of the canvas, as seen by the machine.
+    pub ticks: u16,
+
+    /// How many instructions has the machine executed since the last reset?
+    pub cycles: u16,
+
+    /// Tick of the last message received by the machine.
+    pub last_receive_tick: u16,
+
+    /// Random number generator used by the `rand` instruction.
+    pub rng: Random,
+
+    /// Selected memory bank of each mapped port, set by `select_bank`.
+    /// Ports without an entry use the first bank.
+    pub banks: HashMap<u16, u16>,
+
+    /// Text supplied by the host as UTF-16 code units, consumed by `read_char` and `read_line`.
+    pub input: VecDeque<u16>,
+
+    /// Is the machine waiting for the host to supply input?
+    pub awaiting_input: bool,
+
+    /// Has the host closed the input? Reads no longer wait once the input runs out.
+    pub input_closed: bool,
+
+    /// Host functions invoked by the `syscall` instruction.
+    #[serde(skip)]
+    pub host: Host,
 }
 
 impl Machine {
     /// Creates a new machine.
     pub fn new() -> Machine {
+        Machine::with_layout(MemoryLayout::default())
+    }
+
+    /// Creates a new machine with the given memory layout.
+    pub fn with_layout(layout: MemoryLayout) -> Machine {
+        let mut reg = Registers::new();
+        reg.reset_with(&layout);
+
         Machine {
             id: None,
 
-            mem: Memory::new(),
-            reg: Registers::new(),
+            mem: Memory::with_layout(layout),
+            reg,
 
             events: vec![],
-            inbox: VecDeque::new(),
+            inbox: Mailbox::new(),
             outbox: vec![],
 
             is_debug: false,
@@ -67,6 +113,21 @@ impl Machine {
 
             sleeping: false,
             remaining_sleep_ticks: 0,
+
+            error_handler: None,
+
+            ticks: 0,
+            cycles: 0,
+            last_receive_tick: 0,
+
+            rng: Random::default(),
+            banks: HashMap::new(),
+
+            input: VecDeque::new(),
+            awaiting_input: false,
+            input_closed: false,
+
+            host: Host::default(),
         }
     }
 
@@ -80,27 +141,57 @@ impl Machine {
     pub fn call_stack(&mut self) -> StackManager {
         let mut stack = self.stack();
         stack.sp = FP;
-        stack.min = CALL_STACK_START;
-        stack.max = CALL_STACK_END;
+        stack.min = stack.mem.layout.call_stack_start();
+        stack.max = stack.mem.layout.call_stack_end();
         stack
     }
 
+    /// Can the program invoke the host functions with `syscall`?
+    /// Self-modifying programs may write a `syscall` at any time.
+    pub fn may_call_host(&mut self) -> bool {
+        if !self.host.is_attached() { return false; }
+        if self.mem.self_modifying { return true; }
+
+        let mem = &mut self.mem;
+        mem.decoded.contains(&mem.buffer, &mem.layout, |op| matches!(op, Op::Syscall(_)))
+    }
+
     /// Reset the machine completely.
     pub fn full_reset(&mut self) {
         self.partial_reset();
         self.mem.reset();
+        self.error_handler = None;
         self.inbox.clear();
         self.outbox.clear();
         self.events.clear();
+        self.input.clear();
     }
 
     /// Reset the execution state and execution memory of the machine only.
     pub fn partial_reset(&mut self) {
-        self.reg.reset();
+        self.reg.reset_with(&self.mem.layout);
         self.mem.reset_stacks();
         self.expected_receives = 0;
         self.sleeping = false;
         self.remaining_sleep_ticks = 0;
+        self.cycles = 0;
+        self.last_receive_tick = self.ticks;
+        self.rng.reset();
+        self.banks.clear();
+        self.awaiting_input = false;
+        self.input_closed = false;
+    }
+
+    /// Supply text to the machine, to be read by `read_char` and `read_line`.
+    pub fn provide_input(&mut self, text: &str) {
+        self.input.extend(text.encode_utf16());
+        self.awaiting_input = false;
+    }
+
+    /// No more input will be supplied. Reads return the remaining input, then empty values.
+    pub fn close_input(&mut self) {
+        self.input_closed = true;
+        self.awaiting_input = false;
     }
 }
 
@@ -117,9 +208,15 @@ impl TryFrom<&str> for Machine {
 
     fn try_from(source: &str) -> Result<Self, Self::Error> {
         let parser: Parser = source.try_into()?;
+        Ok(parser.into())
+    }
+}
 
+impl From<Parser> for Machine {
+    fn from(parser: Parser) -> Self {
         let mut machine: Self = parser.ops.into();
         machine.mem.load_symbols(parser.symbols);
-        Ok(machine)
+        machine.error_handler = parser.error_handler;
+        machine
     }
 }
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use snafu::prelude::*;
 use tsify::Tsify;
+use crate::mem::Access;
 
 #[derive(Debug, Snafu, Serialize, Deserialize, PartialEq, Clone, Tsify)]
 #[snafu(visibility(pub))]
@@ -51,4 +52,45 @@ pub enum RuntimeError {
 
     #[snafu(display("index out of bounds. index {index} is over {len}"))]
     IndexOutOfBounds { index: u16, len: u16 },
+
+    #[snafu(display("host function {id} is not available"))]
+    MissingHostFunction { id: u16 },
+
+    #[snafu(display("segmentation fault. {access:?} access to address {addr} is not allowed"))]
+    SegmentationFault { addr: u16, access: Access },
+
+    #[snafu(display("program counter {pc} is outside of the code segment"))]
+    PcOutOfBounds { pc: u16 },
+
+    #[snafu(display("bank {bank} of port {port} is outside of the addressable range"))]
+    InvalidBank { port: u16, bank: u16 },
+
+    #[snafu(display("assertion at {pc} failed. expected {expected}, got {actual}"))]
+    AssertionFailed { pc: u16, expected: u16, actual: u16 },
+}
+
+impl RuntimeError {
+    /// Numeric error code, pushed onto the stack when the error handler is invoked.
+    pub fn code(&self) -> u16 {
+        match self {
+            RuntimeError::StackUnderflow { .. } => 1,
+            RuntimeError::StackOverflow { .. } => 2,
+            RuntimeError::CallStackExceeded => 3,
+            RuntimeError::MissingReturnAddress => 4,
+            RuntimeError::MissingMessageBody => 5,
+            RuntimeError::CannotReadStringFromBytes => 6,
+            RuntimeError::CannotLoadFromMemory => 7,
+            RuntimeError::CannotDivideByZero => 8,
+            RuntimeError::IntegerOverflow => 9,
+            RuntimeError::IntegerUnderflow => 10,
+            RuntimeError::MissingValueToStore => 11,
+            RuntimeError::NotEnoughValues { .. } => 12,
+            RuntimeError::IndexOutOfBounds { .. } => 13,
+            RuntimeError::MissingHostFunction { .. } => 14,
+            RuntimeError::SegmentationFault { .. } => 15,
+            RuntimeError::PcOutOfBounds { .. } => 16,
+            RuntimeError::InvalidBank { .. } => 17,
+            RuntimeError::AssertionFailed { .. } => 18,
+        }
+    }
 }
@@ -1,56 +1,105 @@
-use crate::{Action, Actor, Machine, MAPPED_END, MAPPED_START};
+use crate::{Action, Actor, Machine, RuntimeError};
+use crate::mem::MemoryLayout;
+use crate::RuntimeError::InvalidBank;
 
-const SIZE_PER_PORT: u16 = 0x200;
+/// Size of the mapped address window of each port, which is also the size of a memory bank.
+pub const SIZE_PER_PORT: u16 = 0x200;
 
 pub trait VirtualMemory {
     fn read_virtual(&mut self, addr: u16, count: u16) -> bool;
     fn write_virtual(&mut self, addr: u16, data: Vec<u16>) -> bool;
+
+    /// Select the memory bank of the port.
+    fn select_bank(&mut self, port: u16, bank: u16) -> Result<(), RuntimeError>;
 }
 
 impl VirtualMemory for Machine {
     fn read_virtual(&mut self, addr: u16, count: u16) -> bool {
-        if !is_addr_mapped(addr) { return false; }
+        if !self.mem.layout.is_mapped(addr) { return false; }
 
-        let (address, port) = get_mapped_addr(addr);
+        let (address, port) = self.get_banked_addr(addr);
         self.send_message_to_port(port, Action::Read { address, count });
         self.expected_receives += 1;
         true
     }
 
     fn write_virtual(&mut self, addr: u16, data: Vec<u16>) -> bool {
-        if !is_addr_mapped(addr) { return false; }
+        if !self.mem.layout.is_mapped(addr) { return false; }
 
-        let (address, port) = get_mapped_addr(addr);
+        let (address, port) = self.get_banked_addr(addr);
 
         self.send_message_to_port(port, Action::Write { address, data });
         true
     }
+
+    fn select_bank(&mut self, port: u16, bank: u16) -> Result<(), RuntimeError> {
+        // Every address in the bank must be addressable by the peripheral.
+        let base = bank.checked_mul(SIZE_PER_PORT).ok_or(InvalidBank { port, bank })?;
+        base.checked_add(SIZE_PER_PORT - 1).ok_or(InvalidBank { port, bank })?;
+
+        self.banks.insert(port, bank);
+        Ok(())
+    }
 }
 
-pub fn get_mapped_addr(addr: u16) -> (u16, u16) {
-    let addr_norm = addr - MAPPED_START;
+impl Machine {
+    /// Returns the peripheral address and port of the mapped address, in the selected bank.
+    fn get_banked_addr(&self, addr: u16) -> (u16, u16) {
+        let (offset, port) = get_mapped_addr(&self.mem.layout, addr);
+        let bank = self.banks.get(&port).copied().unwrap_or(0);
 
-    (addr_norm % SIZE_PER_PORT, addr_norm / SIZE_PER_PORT)
+        (bank * SIZE_PER_PORT + offset, port)
+    }
 }
 
-pub fn is_addr_mapped(addr: u16) -> bool {
-    addr >= MAPPED_START && addr <= MAPPED_END
+pub fn get_mapped_addr(layout: &MemoryLayout, addr: u16) -> (u16, u16) {
+    let addr_norm = addr - layout.mapped_start();
+
+    (addr_norm % SIZE_PER_PORT, addr_norm / SIZE_PER_PORT)
 }
 
 #[cfg(test)]
 mod virtual_mem_test {
-    use super::{is_addr_mapped, MAPPED_START, MAPPED_END, get_mapped_addr, SIZE_PER_PORT};
+    use super::{get_mapped_addr, VirtualMemory, SIZE_PER_PORT};
+    use crate::mem::{MemoryLayout, MAPPED_END, MAPPED_START};
+    use crate::{Machine, RuntimeError};
 
     #[test]
     pub fn addr_mapped_test() {
-        assert_eq!(is_addr_mapped(MAPPED_START - 1), false);
-        assert_eq!(is_addr_mapped(MAPPED_START), true);
-        assert_eq!(is_addr_mapped(MAPPED_END), true);
-        assert_eq!(is_addr_mapped(MAPPED_END + 1), false);
-
-        assert_eq!(get_mapped_addr(MAPPED_START), (0, 0));
-        assert_eq!(get_mapped_addr(MAPPED_START + SIZE_PER_PORT - 1), (SIZE_PER_PORT - 1, 0));
-        assert_eq!(get_mapped_addr(MAPPED_START + SIZE_PER_PORT), (0, 1));
-        assert_eq!(get_mapped_addr(MAPPED_START + SIZE_PER_PORT + 1), (1, 1));
+        let l = MemoryLayout::default();
+
+        assert_eq!(l.is_mapped(MAPPED_START - 1), false);
+        assert_eq!(l.is_mapped(MAPPED_START), true);
+        assert_eq!(l.is_mapped(MAPPED_END), true);
+        assert_eq!(l.is_mapped(MAPPED_END + 1), false);
+
+        assert_eq!(get_mapped_addr(&l, MAPPED_START), (0, 0));
+        assert_eq!(get_mapped_addr(&l, MAPPED_START + SIZE_PER_PORT - 1), (SIZE_PER_PORT - 1, 0));
+        assert_eq!(get_mapped_addr(&l, MAPPED_START + SIZE_PER_PORT), (0, 1));
+        assert_eq!(get_mapped_addr(&l, MAPPED_START + SIZE_PER_PORT + 1), (1, 1));
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn tiny_layout_mapped_test() {
+        let l = MemoryLayout::tiny();
+
+        assert_eq!(l.is_mapped(l.mapped_start()), true);
+        assert_eq!(l.is_mapped(MAPPED_START), false);
+        assert_eq!(get_mapped_addr(&l, l.mapped_start() + SIZE_PER_PORT), (0, 1));
+    }
+
+    #[test]
+    pub fn banked_addr_test() -> Result<(), RuntimeError> {
+        let mut m = Machine::new();
+        m.select_bank(1, 3)?;
+
+        assert_eq!(m.get_banked_addr(MAPPED_START + 5), (5, 0));
+        assert_eq!(m.get_banked_addr(MAPPED_START + SIZE_PER_PORT + 5), (3 * SIZE_PER_PORT + 5, 1));
+
+        m.select_bank(1, 0x7F)?;
+        assert_eq!(m.get_banked_addr(MAPPED_START + SIZE_PER_PORT * 2 - 1), (u16::MAX, 1));
+        assert_eq!(m.select_bank(1, 0x80), Err(RuntimeError::InvalidBank { port: 1, bank: 0x80 }));
+
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 extern crate machine;
 
 use clap::Parser;
-use machine::cli::{compile_to_file, run_from_binary_file, run_from_source, Args, Commands};
+use machine::cli::{compile_to_file, format_file, print_graph, run_from_binary_file, run_from_source, run_tests, Args, Commands};
 
 fn main() {
     let args = Args::parse();
@@ -12,7 +12,7 @@ fn main() {
     }
 
     let result = match args.command.unwrap() {
-        Commands::Compile { src, out } => compile_to_file(&src, &out),
+        Commands::Compile { src, out, optimize } => compile_to_file(&src, &out, optimize),
         Commands::Run {
             path,
             from_source,
@@ -24,9 +24,13 @@ fn main() {
                 run_from_binary_file(&path, debug)
             }
         }
+        Commands::Graph { src, calls, json } => print_graph(&src, calls, json),
+        Commands::Fmt { path, write, check } => format_file(&path, write, check),
+        Commands::Test { dir } => run_tests(&dir),
     };
 
     if let Err(error) = result {
         println!("Command line error: {:?}", error);
+        std::process::exit(1);
     }
 }
@@ -0,0 +1,88 @@
+use std::fmt::{Debug, Formatter};
+use std::sync::Arc;
+use crate::mem::{MemoryLayout, PagedBuffer};
+use crate::Op;
+
+/// Instructions of the code segment, decoded ahead of time.
+///
+/// Every address of the code segment holds the instruction that starts there,
+/// so the machine does not decode the same instruction on every tick.
+/// Instructions whose arguments extend past the code segment are not cached.
+///
+/// Writes to the code segment discard the cache, and it is rebuilt on the next decode.
+/// Clones share the decoded instructions.
+#[derive(Clone)]
+pub struct DecodeCache {
+    /// Disable the cache to decode every instruction from memory.
+    pub enabled: bool,
+
+    ops: Option<Arc<[Option<Op>]>>,
+}
+
+impl DecodeCache {
+    pub fn new() -> DecodeCache {
+        DecodeCache { enabled: true, ops: None }
+    }
+
+    /// Returns the decoded instruction at the address, building the cache if needed.
+    pub fn get(&mut self, addr: u16, buffer: &PagedBuffer, layout: &MemoryLayout) -> Option<Op> {
+        if !self.enabled { return None; }
+
+        let ops = self.ops.get_or_insert_with(|| decode_all(buffer, layout));
+        ops.get(addr.checked_sub(layout.code_start())? as usize).copied().flatten()
+    }
+
+    /// Does the code segment contain an instruction that matches?
+    /// Every address is decoded, including the arguments, so this may find instructions that never run.
+    pub fn contains(&mut self, buffer: &PagedBuffer, layout: &MemoryLayout, matches: impl Fn(Op) -> bool) -> bool {
+        if !self.enabled { return decode_all(buffer, layout).iter().flatten().any(|op| matches(*op)); }
+
+        let ops = self.ops.get_or_insert_with(|| decode_all(buffer, layout));
+        ops.iter().flatten().any(|op| matches(*op))
+    }
+
+    /// Discard the decoded instructions.
+    pub fn invalidate(&mut self) {
+        self.ops = None;
+    }
+
+    pub fn is_built(&self) -> bool {
+        self.ops.is_some()
+    }
+}
+
+/// Decode the instruction at every address of the code segment.
+fn decode_all(buffer: &PagedBuffer, layout: &MemoryLayout) -> Arc<[Option<Op>]> {
+    let code = buffer.read(layout.code_start() as usize, layout.code_end() as usize + 1);
+
+    (0..code.len())
+        .map(|start| {
+            let mut args = code[start + 1..].iter();
+            let op = Op::from(code[start]);
+
+            // Leave out the instructions that are cut off by the end of the code segment.
+            if op.arity() > args.len() { return None; }
+
+            Some(op.with_arg(|| *args.next().unwrap_or(&0)))
+        })
+        .collect()
+}
+
+impl Default for DecodeCache {
+    fn default() -> Self {
+        DecodeCache::new()
+    }
+}
+
+impl Debug for DecodeCache {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DecodeCache({})", if self.is_built() { "built" } else { "empty" })
+    }
+}
+
+/// The cache is derived from the memory, so it never makes two memories different.
+impl PartialEq for DecodeCache {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
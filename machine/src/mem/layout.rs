@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+use crate::mem::{Segment, CALL_STACK_SIZE, CODE_SIZE, DATA_SIZE, MAPPED_SIZE, MEMORY_SIZE};
+
+/// Sizes of the memory segments of a machine.
+/// Segments are laid out in order: code, data, memory-mapped, call stack, then the stack.
+/// The stack takes the remaining memory.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryLayout {
+    /// Total memory available.
+    pub memory_size: u16,
+
+    pub code_size: u16,
+    pub data_size: u16,
+    pub mapped_size: u16,
+    pub call_stack_size: u16,
+}
+
+/// Named memory layouts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum LayoutPreset {
+    /// Small memory footprint, for canvases with many small machines.
+    Tiny,
+
+    /// The standard 64K layout.
+    Default,
+
+    /// Large call stack, for deeply recursive programs.
+    DeepRecursion,
+}
+
+impl MemoryLayout {
+    pub fn tiny() -> MemoryLayout {
+        MemoryLayout {
+            memory_size: 0x1000,
+            code_size: 0x400,
+            data_size: 0x200,
+            mapped_size: 0x400,
+            call_stack_size: 0x40,
+        }
+    }
+
+    pub fn deep_recursion() -> MemoryLayout {
+        MemoryLayout {
+            call_stack_size: 0x4000,
+            ..MemoryLayout::default()
+        }
+    }
+
+    /// Do the segments fit in the memory, leaving room for the stack?
+    pub fn is_valid(&self) -> bool {
+        let sizes = [self.code_size, self.data_size, self.mapped_size, self.call_stack_size];
+
+        // Every segment except the memory-mapped segment must be usable.
+        if sizes.iter().enumerate().any(|(i, size)| i != 2 && *size == 0) { return false; }
+
+        let used: u32 = sizes.iter().map(|s| *s as u32).sum();
+        used < self.memory_size as u32
+    }
+
+    pub fn code_start(&self) -> u16 {
+        0
+    }
+
+    pub fn code_end(&self) -> u16 {
+        self.code_start() + self.code_size - 1
+    }
+
+    pub fn data_start(&self) -> u16 {
+        self.code_start() + self.code_size
+    }
+
+    pub fn data_end(&self) -> u16 {
+        self.data_start() + self.data_size - 1
+    }
+
+    pub fn mapped_start(&self) -> u16 {
+        self.data_start() + self.data_size
+    }
+
+    /// The memory-mapped segment is empty if its end is before its start.
+    pub fn mapped_end(&self) -> u16 {
+        (self.mapped_start() + self.mapped_size).wrapping_sub(1)
+    }
+
+    pub fn call_stack_start(&self) -> u16 {
+        self.mapped_start() + self.mapped_size
+    }
+
+    pub fn call_stack_end(&self) -> u16 {
+        self.call_stack_start() + self.call_stack_size - 1
+    }
+
+    pub fn stack_start(&self) -> u16 {
+        self.call_stack_start() + self.call_stack_size
+    }
+
+    pub fn stack_end(&self) -> u16 {
+        self.memory_size - 1
+    }
+
+    /// Is the address within the memory-mapped segment?
+    pub fn is_mapped(&self, addr: u16) -> bool {
+        self.mapped_size > 0 && addr >= self.mapped_start() && addr <= self.mapped_end()
+    }
+
+    /// Returns the segment containing the address, if any.
+    pub fn segment_of(&self, addr: u16) -> Option<Segment> {
+        if addr >= self.memory_size { return None; }
+
+        if addr <= self.code_end() { return Some(Segment::Code); }
+        if addr <= self.data_end() { return Some(Segment::Data); }
+        if self.is_mapped(addr) { return Some(Segment::Mapped); }
+        if addr <= self.call_stack_end() { return Some(Segment::CallStack); }
+
+        Some(Segment::Stack)
+    }
+}
+
+impl Default for MemoryLayout {
+    fn default() -> Self {
+        MemoryLayout {
+            memory_size: MEMORY_SIZE,
+            code_size: CODE_SIZE,
+            data_size: DATA_SIZE,
+            mapped_size: MAPPED_SIZE,
+            call_stack_size: CALL_STACK_SIZE,
+        }
+    }
+}
+
+impl From<LayoutPreset> for MemoryLayout {
+    fn from(preset: LayoutPreset) -> Self {
+        match preset {
+            LayoutPreset::Tiny => MemoryLayout::tiny(),
+            LayoutPreset::Default => MemoryLayout::default(),
+            LayoutPreset::DeepRecursion => MemoryLayout::deep_recursion(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod layout_tests {
+    use super::*;
+    use crate::mem::{CALL_STACK_END, CALL_STACK_START, DATA_END, DATA_START, MAPPED_END, MAPPED_START, STACK_END, STACK_START};
+
+    #[test]
+    fn test_default_layout() {
+        let l = MemoryLayout::default();
+
+        assert_eq!(l.data_start(), DATA_START);
+        assert_eq!(l.data_end(), DATA_END);
+        assert_eq!(l.mapped_start(), MAPPED_START);
+        assert_eq!(l.mapped_end(), MAPPED_END);
+        assert_eq!(l.call_stack_start(), CALL_STACK_START);
+        assert_eq!(l.call_stack_end(), CALL_STACK_END);
+        assert_eq!(l.stack_start(), STACK_START);
+        assert_eq!(l.stack_end(), STACK_END);
+    }
+
+    #[test]
+    fn test_presets() {
+        for preset in [LayoutPreset::Tiny, LayoutPreset::Default, LayoutPreset::DeepRecursion] {
+            assert!(MemoryLayout::from(preset).is_valid(), "{:?} should be valid", preset);
+        }
+
+        let l = MemoryLayout::tiny();
+        assert_eq!(l.segment_of(0x3FF), Some(Segment::Code));
+        assert_eq!(l.segment_of(0x400), Some(Segment::Data));
+        assert_eq!(l.segment_of(0x600), Some(Segment::Mapped));
+        assert_eq!(l.segment_of(0xA00), Some(Segment::CallStack));
+        assert_eq!(l.segment_of(0xA40), Some(Segment::Stack));
+        assert_eq!(l.segment_of(0x1000), None);
+
+        let l = MemoryLayout { mapped_size: 0, ..MemoryLayout::tiny() };
+        assert!(l.is_valid());
+        assert_eq!(l.segment_of(0x600), Some(Segment::CallStack));
+
+        assert!(!MemoryLayout { code_size: 0xF000, data_size: 0x1000, ..MemoryLayout::default() }.is_valid());
+    }
+}
@@ -1,78 +1,154 @@
 use serde::{Deserialize, Serialize};
-use crate::{compile_to_bytecode, Symbols, Op, CALL_STACK_START, CODE_START, MEMORY_SIZE, STACK_START, DATA_START, STACK_END, CALL_STACK_END};
+use crate::{compile_to_bytecode, Symbols, Op, RuntimeError};
+use crate::mem::{Access, DecodeCache, MemoryLayout, PagedBuffer};
+use crate::RuntimeError::SegmentationFault;
 
 /**
  * Memory defines a fixed-size memory area for the program.
+ * The buffer is paged and copy-on-write, so cloning the memory is cheap.
  */
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Memory {
-    pub buffer: Vec<u16>,
+    pub buffer: PagedBuffer,
+
+    /// Allow the program to write to the code segment.
+    #[serde(default)]
+    pub self_modifying: bool,
+
+    /// Sizes of the memory segments.
+    #[serde(default)]
+    pub layout: MemoryLayout,
+
+    /// Instructions decoded from the code segment.
+    #[serde(skip)]
+    pub decoded: DecodeCache,
 }
 
 impl Memory {
     pub fn new() -> Memory {
+        Memory::with_layout(MemoryLayout::default())
+    }
+
+    pub fn with_layout(layout: MemoryLayout) -> Memory {
         Memory {
-            buffer: vec![0; MEMORY_SIZE as usize],
+            buffer: PagedBuffer::new(layout.memory_size as usize),
+            self_modifying: false,
+            layout,
+            decoded: DecodeCache::new(),
         }
     }
 
+    /// Writes are ignored if the address is outside of the memory.
     pub fn set(&mut self, addr: u16, val: u16) {
-        self.buffer[addr as usize] = val;
+        self.invalidate_code(addr, addr);
+        self.buffer.set(addr as usize, val);
     }
 
     /// Reset the entire memory to zero.
     pub fn reset(&mut self) {
-        self.buffer.fill(0)
+        self.decoded.invalidate();
+        self.buffer.clear()
     }
 
     pub fn reset_range(&mut self, from: u16, to: u16) {
-        self.buffer[(from as usize)..(to as usize)].fill(0);
+        self.invalidate_code(from, to);
+        self.buffer.clear_range(from as usize, to as usize);
     }
 
     /// Reset the stack and call stack memory.
     pub fn reset_stacks(&mut self) {
-        self.reset_range(CALL_STACK_START, CALL_STACK_END);
-        self.reset_range(STACK_START, STACK_END);
+        let l = self.layout;
+        self.reset_range(l.call_stack_start(), l.call_stack_end());
+        self.reset_range(l.stack_start(), l.stack_end());
     }
 
+    /// Reading outside of the memory returns zero.
     pub fn get(&self, addr: u16) -> u16 {
-        self.buffer[addr as usize]
+        self.buffer.get(addr as usize).unwrap_or(0)
     }
 
+    /// Reads the values, up to the end of the memory.
     pub fn read(&self, addr: u16, count: u16) -> Vec<u16> {
-        let start = addr as usize;
-
-        self.buffer[start..(start + count as usize)].into()
+        self.buffer.read(addr as usize, addr as usize + count as usize)
     }
 
+    /// Writes the values, up to the end of the memory.
     pub fn write(&mut self, addr: u16, data: &[u16]) {
+        if data.is_empty() { return; }
+        let last = u16::try_from(data.len() - 1).unwrap_or(u16::MAX);
+        self.invalidate_code(addr, addr.saturating_add(last));
+
         for (offset, value) in data.iter().enumerate() {
-            self.buffer[addr as usize + offset] = *value;
+            if !self.buffer.set(addr as usize + offset, *value) { break; }
         }
     }
 
+    /// Returns the decoded instruction at the address, if it is in the code segment.
+    pub fn decoded(&mut self, addr: u16) -> Option<Op> {
+        self.decoded.get(addr, &self.buffer, &self.layout)
+    }
+
+    /// Discard the decoded instructions if the address range overlaps the code segment.
+    fn invalidate_code(&mut self, from: u16, to: u16) {
+        if from <= self.layout.code_end() && to >= self.layout.code_start() {
+            self.decoded.invalidate();
+        }
+    }
+
+    /// Check if the program is allowed to access the address range.
+    pub fn check_access(&self, addr: u16, count: u16, access: Access) -> Result<(), RuntimeError> {
+        for offset in 0..count {
+            let Some(addr) = addr.checked_add(offset) else {
+                return Err(SegmentationFault { addr: u16::MAX, access });
+            };
+
+            let allowed = self.layout.segment_of(addr).is_some_and(|s| s.allows(access, self.self_modifying));
+            if !allowed { return Err(SegmentationFault { addr, access }); }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the values on behalf of the program.
+    pub fn load(&self, addr: u16, count: u16) -> Result<Vec<u16>, RuntimeError> {
+        self.check_access(addr, count, Access::Read)?;
+        Ok(self.read(addr, count))
+    }
+
+    /// Writes the values on behalf of the program.
+    pub fn store(&mut self, addr: u16, data: &[u16]) -> Result<(), RuntimeError> {
+        // No address range can hold more values than the memory.
+        let Ok(count) = u16::try_from(data.len()) else {
+            return Err(SegmentationFault { addr: u16::MAX, access: Access::Write });
+        };
+
+        self.check_access(addr, count, Access::Write)?;
+        self.write(addr, data);
+        Ok(())
+    }
+
     pub fn read_code(&self, count: u16) -> Vec<u16> {
-        self.read(CODE_START, count)
+        self.read(self.layout.code_start(), count)
     }
 
     pub fn read_stack(&self, count: u16) -> Vec<u16> {
-        self.read(STACK_START, count)
+        self.read(self.layout.stack_start(), count)
     }
 
     pub fn read_call_stack(&self, count: u16) -> Vec<u16> {
-        self.read(CALL_STACK_START, count)
+        self.read(self.layout.call_stack_start(), count)
     }
 
     pub fn read_data(&self, count: u16) -> Vec<u16> {
-        self.read(DATA_START, count)
+        self.read(self.layout.data_start(), count)
     }
 
     pub fn load_code(&mut self, ops: Vec<Op>) {
-        self.write(CODE_START, &compile_to_bytecode(ops))
+        self.write(self.layout.code_start(), &compile_to_bytecode(ops))
     }
 
     pub fn load_symbols(&mut self, symbols: Symbols) {
-        self.write(DATA_START, &symbols.bytes());
+        self.write(self.layout.data_start(), &symbols.bytes());
     }
 }
 
@@ -80,6 +156,7 @@ impl Memory {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::{CALL_STACK_START, CODE_START, DATA_START, MEMORY_SIZE, STACK_END, STACK_START};
 
     #[test]
     fn test_memset() {
@@ -102,7 +179,43 @@ mod tests {
     fn test_load_code() {
         let mut m = Memory::new();
         m.load_code(vec![Op::Push(5), Op::Push(10)]);
-        assert_eq!(&m.buffer[0..4], [0x01, 5, 0x01, 10])
+        assert_eq!(m.buffer.read(0, 4), [0x01, 5, 0x01, 10])
+    }
+
+    #[test]
+    fn test_out_of_range() {
+        let mut m = Memory::new();
+        m.set(MEMORY_SIZE, 1);
+        m.write(MEMORY_SIZE - 1, &[1, 2, 3]);
+
+        assert_eq!(m.get(MEMORY_SIZE), 0);
+        assert_eq!(m.read(MEMORY_SIZE - 1, 3), [1]);
+    }
+
+    #[test]
+    fn test_check_access() {
+        let mut m = Memory::new();
+
+        assert_eq!(m.check_access(CODE_START, 2, Access::Read), Ok(()));
+        assert_eq!(m.check_access(CODE_START, 2, Access::Write), Err(SegmentationFault { addr: CODE_START, access: Access::Write }));
+        assert_eq!(m.check_access(DATA_START - 1, 2, Access::Write), Err(SegmentationFault { addr: DATA_START - 1, access: Access::Write }));
+        assert_eq!(m.check_access(DATA_START, 2, Access::Execute), Err(SegmentationFault { addr: DATA_START, access: Access::Execute }));
+        assert_eq!(m.check_access(CALL_STACK_START, 1, Access::Write), Err(SegmentationFault { addr: CALL_STACK_START, access: Access::Write }));
+        assert_eq!(m.check_access(STACK_START, 1, Access::Write), Err(SegmentationFault { addr: STACK_START, access: Access::Write }));
+        assert_eq!(m.check_access(STACK_START, 1, Access::Read), Ok(()));
+        assert_eq!(m.check_access(STACK_END, 2, Access::Read), Err(SegmentationFault { addr: MEMORY_SIZE, access: Access::Read }));
+
+        m.self_modifying = true;
+        assert_eq!(m.check_access(CODE_START, 2, Access::Write), Ok(()));
+    }
+
+    #[test]
+    fn test_store_too_many_values() {
+        let mut m = Memory::new();
+        let data = vec![1; u16::MAX as usize + 1];
+
+        assert_eq!(m.store(DATA_START, &data), Err(SegmentationFault { addr: u16::MAX, access: Access::Write }));
+        assert_eq!(m.read_data(1), [0]);
     }
 
     #[test]
@@ -121,4 +234,17 @@ mod tests {
         assert_eq!(m.read_stack(3), [0, 0, 0]);
         assert_eq!(m.read_call_stack(3), [0, 0, 0]);
     }
+
+    #[test]
+    fn test_deserialize_without_protection_fields() {
+        let mut value = serde_json::to_value(Memory::new()).expect("cannot serialize the memory");
+        let fields = value.as_object_mut().expect("memory should be an object");
+        fields.remove("self_modifying");
+        fields.remove("layout");
+
+        // Memory saved before the protection fields existed can still be loaded.
+        let m: Memory = serde_json::from_value(value).expect("cannot deserialize the memory");
+        assert!(!m.self_modifying);
+        assert_eq!(m.layout, MemoryLayout::default());
+    }
 }
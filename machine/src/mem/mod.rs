@@ -2,8 +2,14 @@ pub mod memory;
 pub mod stack;
 pub mod segments;
 pub mod string;
+pub mod layout;
+pub mod pages;
+pub mod decoded;
 
 pub use self::memory::*;
 pub use self::stack::*;
 pub use self::segments::*;
 pub use self::string::*;
+pub use self::layout::*;
+pub use self::pages::*;
+pub use self::decoded::*;
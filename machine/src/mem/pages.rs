@@ -0,0 +1,237 @@
+use std::sync::Arc;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Number of words in a page.
+pub const PAGE_SIZE: usize = 0x100;
+
+type Page = Arc<[u16; PAGE_SIZE]>;
+
+static ZERO_PAGE: [u16; PAGE_SIZE] = [0; PAGE_SIZE];
+
+/// Sparse, copy-on-write buffer of words.
+///
+/// Pages are only allocated once a non-zero value is written to them.
+/// Cloning the buffer shares every page with the clone; a page is copied on its first write.
+#[derive(Debug, Clone, Default)]
+pub struct PagedBuffer {
+    pages: Vec<Option<Page>>,
+    len: usize,
+}
+
+impl PagedBuffer {
+    /// Creates a zero-filled buffer with the given number of words.
+    pub fn new(len: usize) -> PagedBuffer {
+        PagedBuffer { pages: vec![None; (len + PAGE_SIZE - 1) / PAGE_SIZE], len }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the value at the index, if it is within the buffer.
+    pub fn get(&self, index: usize) -> Option<u16> {
+        if index >= self.len { return None; }
+
+        match &self.pages[index / PAGE_SIZE] {
+            Some(page) => Some(page[index % PAGE_SIZE]),
+            None => Some(0),
+        }
+    }
+
+    /// Sets the value at the index. Returns false if the index is outside of the buffer.
+    pub fn set(&mut self, index: usize, value: u16) -> bool {
+        if index >= self.len { return false; }
+
+        let slot = &mut self.pages[index / PAGE_SIZE];
+
+        // Writing zero to an unallocated page is a no-op.
+        if slot.is_none() && value == 0 { return true; }
+
+        let page = slot.get_or_insert_with(|| Arc::new(ZERO_PAGE));
+        Arc::make_mut(page)[index % PAGE_SIZE] = value;
+        true
+    }
+
+    /// Reads the values between the indices, up to the end of the buffer.
+    pub fn read(&self, start: usize, end: usize) -> Vec<u16> {
+        let end = end.min(self.len);
+        let start = start.min(end);
+
+        let mut values = Vec::with_capacity(end - start);
+        let mut index = start;
+
+        while index < end {
+            let page_end = ((index / PAGE_SIZE + 1) * PAGE_SIZE).min(end);
+            values.extend_from_slice(&self.page(index / PAGE_SIZE)[index % PAGE_SIZE..][..page_end - index]);
+            index = page_end;
+        }
+
+        values
+    }
+
+    /// Resets the values between the indices to zero.
+    pub fn clear_range(&mut self, start: usize, end: usize) {
+        let end = end.min(self.len);
+        let mut index = start.min(end);
+
+        while index < end {
+            let page_index = index / PAGE_SIZE;
+            let page_end = ((page_index + 1) * PAGE_SIZE).min(end);
+
+            // Release pages that are entirely cleared.
+            if index % PAGE_SIZE == 0 && page_end - index == PAGE_SIZE {
+                self.pages[page_index] = None;
+            } else if let Some(page) = &mut self.pages[page_index] {
+                Arc::make_mut(page)[index % PAGE_SIZE..][..page_end - index].fill(0);
+            }
+
+            index = page_end;
+        }
+    }
+
+    /// Resets the entire buffer to zero, releasing every page.
+    pub fn clear(&mut self) {
+        self.pages.fill(None);
+    }
+
+    pub fn to_vec(&self) -> Vec<u16> {
+        self.read(0, self.len)
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// How many pages hold data?
+    pub fn allocated_pages(&self) -> usize {
+        self.pages.iter().filter(|p| p.is_some()).count()
+    }
+
+    /// Returns the values of the page, up to the end of the buffer.
+    pub fn page(&self, page_index: usize) -> &[u16] {
+        let start = page_index * PAGE_SIZE;
+        let len = self.len.saturating_sub(start).min(PAGE_SIZE);
+
+        match self.pages.get(page_index) {
+            Some(Some(page)) => &page[..len],
+            _ => &ZERO_PAGE[..len],
+        }
+    }
+
+    /// Is the page known to be identical in both buffers, without comparing its values?
+    /// This is the case when the page is shared between clones, or unallocated in both.
+    pub fn shares_page(&self, other: &PagedBuffer, page_index: usize) -> bool {
+        match (self.pages.get(page_index), other.pages.get(page_index)) {
+            (Some(Some(a)), Some(Some(b))) => Arc::ptr_eq(a, b),
+            (Some(None), Some(None)) => true,
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+impl PartialEq for PagedBuffer {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && (0..self.page_count()).all(|i| {
+            self.shares_page(other, i) || self.page(i) == other.page(i)
+        })
+    }
+}
+
+impl From<&[u16]> for PagedBuffer {
+    fn from(values: &[u16]) -> Self {
+        let mut buffer = PagedBuffer::new(values.len());
+
+        for (index, value) in values.iter().enumerate() {
+            buffer.set(index, *value);
+        }
+
+        buffer
+    }
+}
+
+/// Serialized as a flat array of words, same as a dense buffer.
+impl Serialize for PagedBuffer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_vec().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for PagedBuffer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let values: Vec<u16> = Vec::deserialize(deserializer)?;
+        Ok(values.as_slice().into())
+    }
+}
+
+#[cfg(test)]
+mod pages_tests {
+    use super::*;
+
+    #[test]
+    fn test_sparse_pages() {
+        let mut b = PagedBuffer::new(0x1000);
+        assert_eq!(b.page_count(), 0x10);
+        assert_eq!(b.allocated_pages(), 0);
+
+        b.set(0x0, 0);
+        assert_eq!(b.allocated_pages(), 0);
+
+        b.set(0x101, 5);
+        assert_eq!(b.allocated_pages(), 1);
+        assert_eq!(b.get(0x101), Some(5));
+        assert_eq!(b.get(0x100), Some(0));
+        assert_eq!(b.get(0x1000), None);
+        assert!(!b.set(0x1000, 1));
+    }
+
+    #[test]
+    fn test_read_across_pages() {
+        let mut b = PagedBuffer::new(0x201);
+        b.set(0xFF, 1);
+        b.set(0x100, 2);
+        b.set(0x200, 3);
+
+        assert_eq!(b.read(0xFE, 0x102), [0, 1, 2, 0]);
+        assert_eq!(b.read(0x1FF, 0x300), [0, 3]);
+        assert!(b.read(0x300, 0x400).is_empty());
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let mut a = PagedBuffer::new(0x300);
+        a.set(0x10, 1);
+        a.set(0x110, 2);
+
+        let mut b = a.clone();
+        assert!(b.shares_page(&a, 0) && b.shares_page(&a, 1));
+
+        b.set(0x10, 10);
+        assert_eq!(a.get(0x10), Some(1));
+        assert_eq!(b.get(0x10), Some(10));
+        assert!(!b.shares_page(&a, 0));
+        assert!(b.shares_page(&a, 1));
+        assert_ne!(a, b);
+
+        b.set(0x10, 1);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_clear_range() {
+        let mut b: PagedBuffer = [1; 0x250].as_slice().into();
+        b.clear_range(0xF0, 0x210);
+
+        assert_eq!(b.read(0xEE, 0xF2), [1, 1, 0, 0]);
+        assert_eq!(b.read(0x20E, 0x212), [0, 0, 1, 1]);
+        assert_eq!(b.allocated_pages(), 2);
+
+        b.clear();
+        assert_eq!(b.allocated_pages(), 0);
+        assert_eq!(b, PagedBuffer::new(0x250));
+    }
+}
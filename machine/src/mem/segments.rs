@@ -1,3 +1,10 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+// The constants below describe the default memory layout.
+// Machines may use a different layout, see `MemoryLayout`.
+
 /// Total memory available.
 pub const MEMORY_SIZE: u16 = 0xFFFF;
 
@@ -26,3 +33,37 @@ pub const CALL_STACK_END: u16 = CALL_STACK_START + CALL_STACK_SIZE - 1;
 // Stack segment
 pub const STACK_START: u16 = CALL_STACK_END + 1;
 pub const STACK_END: u16 = MEMORY_SIZE - 1;
+
+/// Kinds of memory access made by the program.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum Access {
+    Read,
+    Write,
+    Execute,
+}
+
+/// Memory segments of the machine.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Code,
+    Data,
+    Mapped,
+    CallStack,
+    Stack,
+}
+
+impl Segment {
+    /// Can the program access this segment?
+    /// The stacks are managed by the stack instructions, `call` and `return`, so programs can only read from them.
+    /// Writing to the code segment is only allowed for self-modifying programs.
+    pub fn allows(self, access: Access, self_modifying: bool) -> bool {
+        match (self, access) {
+            (Segment::Code, Access::Write) => self_modifying,
+            (Segment::Code, _) => true,
+            (_, Access::Execute) => false,
+            (Segment::CallStack | Segment::Stack, Access::Write) => false,
+            _ => true,
+        }
+    }
+}
@@ -3,7 +3,7 @@ use snafu::prelude::*;
 use crate::mem::Memory;
 use crate::register::{Register, Register::SP, Registers};
 
-use crate::{RuntimeError, STACK_END, STACK_START};
+use crate::RuntimeError;
 use crate::machine::runtime_error::{StackOverflowSnafu, StackUnderflowSnafu};
 
 #[derive(Debug)]
@@ -26,7 +26,8 @@ pub struct StackManager<'a> {
 
 impl<'a> StackManager<'a> {
     pub fn new(mem: &'a mut Memory, reg: &'a mut Registers) -> StackManager<'a> {
-        StackManager { mem, reg, min: STACK_START, max: STACK_END, sp: SP, is_debug: false }
+        let (min, max) = (mem.layout.stack_start(), mem.layout.stack_end());
+        StackManager { mem, reg, min, max, sp: SP, is_debug: false }
     }
 
     pub fn top(&self) -> u16 {
@@ -103,6 +104,7 @@ impl<'a> StackManager<'a> {
 mod tests {
     use super::*;
     use crate::machine::Machine;
+    use crate::STACK_END;
 
     #[test]
     fn test_stack() -> Result<(), RuntimeError> {
@@ -1,6 +1,6 @@
 extern crate snafu;
 
-use crate::mem::{DATA_END, DATA_START, Memory};
+use crate::mem::Memory;
 use crate::RuntimeError;
 use crate::RuntimeError::CannotReadStringFromBytes;
 
@@ -11,7 +11,8 @@ pub struct StringManager<'a> {
 
 impl<'a> StringManager<'a> {
     fn new(mem: &'a mut Memory) -> StringManager<'a> {
-        StringManager { mem, top: DATA_START }
+        let top = mem.layout.data_start();
+        StringManager { mem, top }
     }
 
     /// Add the given data to the data section.
@@ -29,22 +30,19 @@ impl<'a> StringManager<'a> {
     }
 
 
-    /// Get the string at the given address.
+    /// Decode the UTF-16 code units into a string.
+    /// Fails on unpaired surrogates.
     pub fn get_str_from_bytes(&self, v16: Vec<u16>) -> Result<String, RuntimeError> {
-        // TODO: Properly decode high UTF-8 bytes such as emojis.
-        let v8: Vec<u8> = v16.iter().map(|&x| x as u8).collect();
-
-        String::from_utf8(v8).map_err(|_| CannotReadStringFromBytes)
+        String::from_utf16(&v16).map_err(|_| CannotReadStringFromBytes)
     }
 
     /// Get the string bytes until the null terminator.
-    /// TODO: add tests for get_str_bytes
     pub fn get_str_bytes(&self, addr: u16) -> Vec<u16> {
         let mut data = vec![];
 
         for i in addr.. {
             // We've reached the end of the data section.
-            if i > DATA_END { break; }
+            if i > self.mem.layout.data_end() { break; }
 
             // Read the value at the current address.
             let v = self.mem.get(i);
@@ -69,8 +67,29 @@ impl WithStringManager for Memory {
     }
 }
 
+/// Encode the string as null-terminated UTF-16.
+/// Characters above U+FFFF take two words, as a surrogate pair.
 pub fn str_to_u16(s: &str) -> Vec<u16> {
-    let mut v: Vec<u16> = s.chars().map(|c| c as u16).collect();
+    let mut v: Vec<u16> = s.encode_utf16().collect();
     v.push(0x00);
     v
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf16_round_trip() {
+        let mut mem = Memory::new();
+        let addr = mem.string().add_str("héllo 🎉");
+
+        // The emoji is stored as a surrogate pair.
+        let bytes = mem.string().get_str_bytes(addr);
+        assert_eq!(bytes.len(), 8);
+        assert_eq!(bytes[6..], [0xD83C, 0xDF89]);
+
+        assert_eq!(mem.string().get_str_from_bytes(bytes), Ok("héllo 🎉".into()));
+        assert_eq!(mem.string().get_str_from_bytes(vec![0xD83C]), Err(CannotReadStringFromBytes));
+    }
+}
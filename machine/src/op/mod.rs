@@ -1,136 +1,302 @@
 extern crate poom_macros;
 
-use strum_macros::{FromRepr, EnumString, Display};
-use poom_macros::{Arity, InsertArgs, FieldValues, VariantIndex};
+use strum_macros::{FromRepr, EnumString, EnumVariantNames, Display};
+use poom_macros::{Arity, InsertArgs, FieldValues, VariantIndex, StackEffect, Docs};
 
 pub use crate::compile::compile_to_bytecode;
 
 pub mod convert;
 
-#[derive(Debug, Copy, Clone, PartialEq, FromRepr, EnumString, Arity, InsertArgs, FieldValues, VariantIndex, Display)]
+#[derive(Debug, Copy, Clone, PartialEq, FromRepr, EnumString, Arity, InsertArgs, FieldValues, VariantIndex, StackEffect, Docs, EnumVariantNames, Display)]
 #[strum(serialize_all = "snake_case")]
 #[repr(u16)]
 pub enum Op {
+    /// No operation. The assembler skips it.
+    #[stack(pop = 0, push = 0)]
     Noop,
 
+    /// Push the value onto the stack.
+    #[stack(pop = 0, push = 1)]
     Push(u16),
+
+    /// Remove the value at the top of the stack.
+    #[stack(pop = 1, push = 0)]
     Pop,
 
     /// Push the null-terminated string from the specified address onto the stack.
+    #[stack(unknown)]
     LoadString(u16),
 
     /// Push data from the specified address onto the stack.
+    #[stack(pop = 0, push = 1)]
     Load(u16),
 
     /// Pop data from the stack and store it into the specified address.
+    #[stack(pop = 1, push = 0)]
     Store(u16),
 
     /// Pop the address from the stack, then write n values to the address.
+    /// Writes fewer values if the stack runs out, so the number of values it pops is only known at runtime.
+    #[stack(unknown)]
     Write(u16),
 
     /// Pop the address from the stack, then read n values to the address.
+    #[stack(pop = 1, push = f0)]
     Read(u16),
 
     /// Duplicates the value at the top of the stack.
     /// Makes a copy of the top value and pushes it onto the stack.
     /// [1, 2, 3] -> [1, 2, 3, 3]
+    #[stack(pop = 1, push = 2)]
     Dup,
 
     /// Swaps the positions of the top two values on the stack.
+    /// A single value is left in place.
     /// [1, 2, 3] -> [1, 3, 2]
+    #[stack(pop = 1, push = 1)]
     Swap,
 
     /// Duplicates the second value from the top of the stack and pushes it onto the stack.
     /// [1, 2, 3] -> [1, 2, 3, 2]
+    #[stack(pop = 2, push = 3)]
     Over,
 
     /// Rotate the top three values on the stack.
     /// [1, 2, 3] -> [2, 3, 1]
+    #[stack(pop = 3, push = 3)]
     Rotate,
 
     /// Removes the second value from the top of the stack.
     /// [1, 2, 3] -> [1, 3]
+    #[stack(pop = 2, push = 1)]
     Nip,
 
     /// Takes the top value from the stack and inserts it one position below the top.
     /// [1, 2, 3] -> [1, 3, 2, 3]
+    #[stack(pop = 2, push = 3)]
     Tuck,
 
     /// Picks the nth value from the top of the stack and push it onto the stack.
     /// pick(0) [1, 2, 3] -> [1, 2, 3, 3] (same as dup)
     /// pick(1) [1, 2, 3] -> [1, 2, 3, 2] (same as over)
+    #[stack(pop = f0 + 1, push = f0 + 2)]
     Pick(u16),
 
+    /// Increment the value at the top of the stack.
+    #[stack(pop = 1, push = 1)]
     Inc,
+
+    /// Decrement the value at the top of the stack. Stops at zero.
+    #[stack(pop = 1, push = 1)]
     Dec,
 
+    /// Pop two values and push their sum.
+    /// [3, 4] -> [7]
+    #[stack(pop = 2, push = 1)]
     Add,
+
+    /// Pop two values and push the difference, subtracting the top value.
+    /// [7, 4] -> [3]
+    #[stack(pop = 2, push = 1)]
     Sub,
+
+    /// Pop two values and push their product.
+    #[stack(pop = 2, push = 1)]
     Mul,
+
+    /// Pop two values and push the quotient, dividing by the top value.
+    /// [8, 2] -> [4]
+    #[stack(pop = 2, push = 1)]
     Div,
+
+    /// Pop two values and push the remainder, dividing by the top value.
+    #[stack(pop = 2, push = 1)]
     Mod,
 
     /// Jump to the address.
+    #[stack(pop = 0, push = 0)]
     Jump(u16),
 
     /// Jump to the address if the previous value in the stack is zero.
+    #[stack(pop = 1, push = 0)]
     JumpZero(u16),
 
     /// Jump to the address if the previous value in the stack is not zero.
+    #[stack(pop = 1, push = 0)]
     JumpNotZero(u16),
 
+    /// Pop two values, then push 1 if they are equal, or 0 otherwise.
+    #[stack(pop = 2, push = 1)]
     Equal,
+
+    /// Pop two values, then push 1 if they differ, or 0 otherwise.
+    #[stack(pop = 2, push = 1)]
     NotEqual,
+
+    /// Pop two values, then push 1 if the deeper value is less than the top value.
+    /// [1, 2] -> [1]
+    #[stack(pop = 2, push = 1)]
     LessThan,
+
+    /// Pop two values, then push 1 if the deeper value is less than or equal to the top value.
+    #[stack(pop = 2, push = 1)]
     LessThanOrEqual,
+
+    /// Pop two values, then push 1 if the deeper value is greater than the top value.
+    #[stack(pop = 2, push = 1)]
     GreaterThan,
+
+    /// Pop two values, then push 1 if the deeper value is greater than or equal to the top value.
+    #[stack(pop = 2, push = 1)]
     GreaterThanOrEqual,
 
     /// Print the text at the memory address of operand.
+    #[stack(unknown)]
     Print,
 
     /// Stores the PC on the call stack and jumps to the address.
+    #[stack(pop = 0, push = 0)]
     Call(u16),
 
     /// Pop the return address from the call stack and jumps to it.
+    #[stack(pop = 0, push = 0)]
     Return,
 
     /// Send a message to the specified machine
     /// Send(Port, Size)
+    #[stack(pop = f1, push = 0)]
     Send(u16, u16),
 
     /// Push the received bytes onto the stack.
+    #[stack(unknown)]
     Receive,
 
     /// Bitwise AND (&)
+    #[stack(pop = 2, push = 1)]
     And,
 
     /// Bitwise OR (|)
+    #[stack(pop = 2, push = 1)]
     Or,
 
     /// Bitwise XOR (^)
+    #[stack(pop = 2, push = 1)]
     Xor,
 
     /// Bitwise NOT (~)
+    #[stack(pop = 1, push = 1)]
     Not,
 
     /// Bitwise Left Shift (<<)
+    #[stack(pop = 2, push = 1)]
     LeftShift,
 
     /// Bitwise Right Shift (>>)
+    #[stack(pop = 2, push = 1)]
     RightShift,
 
     /// Pause the execution for X milliseconds
+    #[stack(pop = 0, push = 0)]
     SleepMs(u16),
     
     /// Pause the execution for X ticks
+    #[stack(pop = 0, push = 0)]
     SleepTick(u16),
 
     /// Halt the program.
+    #[stack(pop = 0, push = 0)]
     Halt,
 
     /// End-of-file marker.
+    #[stack(pop = 0, push = 0)]
     Eof,
+
+    // Operations added after the end-of-file marker keep the opcodes of compiled programs.
+
+    /// Invoke the host function with the given number.
+    /// The host function reads and writes its values on the stack.
+    #[stack(unknown)]
+    Syscall(u16),
+
+    /// Push a random value onto the stack.
+    #[stack(pop = 0, push = 1)]
+    Rand,
+
+    /// Pop the max and min values, then push a random value between min (inclusive) and max (exclusive).
+    /// [1, 7] -> [4]
+    #[stack(pop = 2, push = 1)]
+    RandRange,
+
+    /// Push the current tick of the canvas.
+    #[stack(pop = 0, push = 1)]
+    Ticks,
+
+    /// Push how many instructions the machine has executed before this one.
+    #[stack(pop = 0, push = 1)]
+    Cycles,
+
+    /// Push how many ticks have passed since the last message was received.
+    #[stack(pop = 0, push = 1)]
+    TicksSinceReceive,
+
+    /// Pop the bank number, then select that bank for the memory-mapped port.
+    /// Mapped addresses of the port are offset by the size of the bank.
+    #[stack(pop = 1, push = 0)]
+    SelectBank(u16),
+
+    /// Push the length of the null-terminated string at the address, in UTF-16 code units.
+    #[stack(pop = 0, push = 1)]
+    StrLen(u16),
+
+    /// Pop the index, then push the UTF-16 code unit at that index of the string at the address.
+    /// [2] -> ['l'] for "hello"
+    #[stack(pop = 1, push = 1)]
+    CharAt(u16),
+
+    /// Pop the value and print it as an unsigned decimal.
+    #[stack(pop = 1, push = 0)]
+    PrintInt,
+
+    /// Pop the value and print it in lowercase hexadecimal.
+    #[stack(pop = 1, push = 0)]
+    PrintHex,
+
+    /// Pop the UTF-16 code unit and print it as a character.
+    #[stack(pop = 1, push = 0)]
+    PrintChar,
+
+    /// Print the template string at the address, with its placeholders replaced by values from the stack.
+    /// Supports `%d`, `%x`, `%c` and `%s`, where `%s` is the address of a string. `%%` prints a percent sign.
+    /// The first placeholder takes the deepest value: [3, 4] -> "3 + 4" for "%d + %d"
+    #[stack(unknown)]
+    PrintFmt(u16),
+
+    /// Push the next character of the host input, as a UTF-16 code unit.
+    /// Waits for the host to supply input. Pushes zero once the input is closed.
+    #[stack(pop = 0, push = 1)]
+    ReadChar,
+
+    /// Store the next line of the host input at the address as a null-terminated string, then push its length.
+    /// The line break is not stored. Waits until the host supplies a whole line, or closes the input.
+    #[stack(pop = 0, push = 1)]
+    ReadLine(u16),
+
+    /// Pop the value and fail with an assertion error if it is zero.
+    #[stack(pop = 1, push = 0)]
+    Assert,
+
+    /// Pop the expected value, then the actual value, and fail with an assertion error if they differ.
+    /// [42, 42] -> []
+    #[stack(pop = 2, push = 0)]
+    AssertEq,
+}
+
+/// How many values an operation pops from and pushes onto the stack.
+/// Operations that only read values below the top, such as `over`, count them as popped and pushed back.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StackEffect {
+    pub pops: u32,
+    pub pushes: u32,
 }
 
 impl Op {
@@ -168,6 +334,11 @@ mod tests {
 
         // Convert instruction to opcode and back.
         assert_eq!(Op::from(Op::Push(12).opcode()), Op::Push(0));
+
+        // Opcodes of compiled programs do not change when operations are added.
+        assert_eq!(Op::Halt.opcode(), 0x2C);
+        assert_eq!(Op::Eof.opcode(), 0x2D);
+        assert_eq!(Op::Syscall(0).opcode(), 0x2E);
     }
 
     #[test]
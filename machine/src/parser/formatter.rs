@@ -0,0 +1,260 @@
+use crate::{Scanner, Token, TokenType};
+
+const INDENT: &str = "    ";
+
+/// A line of the formatted source.
+#[derive(Debug, Clone, PartialEq)]
+enum Line {
+    Blank,
+
+    /// A line with only a comment.
+    Comment(String),
+
+    Label { name: String, comment: Option<String> },
+
+    /// Instruction and its operands.
+    Instruction { parts: Vec<String>, comment: Option<String> },
+
+    /// Definition keyword, such as `.string`, followed by its name and value.
+    Definition { parts: Vec<String>, comment: Option<String> },
+}
+
+impl Line {
+    fn parts(&self) -> Option<&Vec<String>> {
+        match self {
+            Line::Instruction { parts, .. } | Line::Definition { parts, .. } => Some(parts),
+            _ => None,
+        }
+    }
+
+    fn comment(&self) -> Option<&String> {
+        match self {
+            Line::Label { comment, .. } | Line::Instruction { comment, .. } | Line::Definition { comment, .. } => comment.as_ref(),
+            Line::Comment(comment) => Some(comment),
+            Line::Blank => None,
+        }
+    }
+
+    /// Can the line be aligned with the line before it?
+    fn aligns_with(&self, other: &Line) -> bool {
+        matches!(
+            (self, other),
+            (Line::Instruction { .. }, Line::Instruction { .. }) | (Line::Definition { .. }, Line::Definition { .. })
+        )
+    }
+}
+
+/// Format the assembly source in the canonical style.
+///
+/// Instructions are lowercase and indented under their labels.
+/// Operands, definitions and trailing comments are aligned with the neighbouring lines.
+/// Sources that cannot be scanned are returned as is.
+pub fn format_source(source: &str) -> String {
+    let mut scanner = Scanner::new(source);
+    if scanner.scan_tokens().is_err() { return source.to_string(); }
+
+    let lines = collect_lines(source, &scanner.tokens);
+    render(&lines)
+}
+
+/// Group the tokens into lines, and recover the comments that the scanner skips.
+fn collect_lines(source: &str, tokens: &[Token]) -> Vec<Line> {
+    let mut lines = vec![];
+    let mut tokens = tokens.iter().peekable();
+    let mut start = 0;
+
+    while start <= source.len() {
+        let mut end = line_end(source, start);
+
+        // Byte offset of the end of the last token on the line.
+        let mut consumed = start;
+        let mut line_tokens = vec![];
+
+        loop {
+            while let Some(token) = tokens.next_if(|token| token.offset < end) {
+                consumed = consumed.max(token.offset + token.lexeme.trim_end().len());
+                line_tokens.push(token);
+            }
+
+            // A multi-line string continues the line.
+            if consumed <= end { break; }
+            end = line_end(source, consumed);
+        }
+
+        // Comments start after the last token on the line.
+        let text = &source[start..end];
+        let rest = &text[consumed - start..];
+        let comment = rest.find(';').map(|i| rest[i..].trim_end().to_string());
+
+        lines.extend(classify(&line_tokens, comment, text));
+        start = end + 1;
+    }
+
+    lines
+}
+
+/// Byte offset of the end of the line.
+fn line_end(source: &str, from: usize) -> usize {
+    source[from..].find('\n').map_or(source.len(), |i| from + i)
+}
+
+/// Build the formatted lines from the tokens of a source line.
+fn classify(tokens: &[&Token], comment: Option<String>, text: &str) -> Vec<Line> {
+    let lexemes: Vec<String> = tokens.iter().map(|token| token.lexeme.trim().to_string()).collect();
+
+    let Some(first) = tokens.first() else {
+        return match comment {
+            Some(comment) => vec![Line::Comment(comment)],
+
+            // Keep the text that the scanner skips, such as unknown directives.
+            None if !text.trim().is_empty() => vec![Line::Comment(text.trim().to_string())],
+
+            None => vec![Line::Blank],
+        };
+    };
+
+    match first.token_type {
+        TokenType::LabelDefinition if tokens.len() > 1 => {
+            // An instruction on the same line as the label is moved to its own line.
+            let rest: Vec<&Token> = tokens[1..].to_vec();
+            let mut lines = vec![Line::Label { name: lexemes[0].clone(), comment: None }];
+            lines.extend(classify(&rest, comment, text));
+            lines
+        }
+
+        TokenType::LabelDefinition => vec![Line::Label { name: lexemes[0].clone(), comment }],
+
+        TokenType::Instruction => {
+            let mut parts = lexemes;
+            parts[0] = parts[0].to_lowercase();
+            vec![Line::Instruction { parts, comment }]
+        }
+
+        TokenType::StringDefinition | TokenType::ValueDefinition | TokenType::ErrorHandlerDefinition => {
+            vec![Line::Definition { parts: lexemes, comment }]
+        }
+
+        _ => vec![Line::Instruction { parts: lexemes, comment }],
+    }
+}
+
+/// Render the lines, aligning the columns of the neighbouring instructions and definitions.
+fn render(lines: &[Line]) -> String {
+    let lines = lines_without_repeated_blanks(lines);
+    let indents = indents(&lines);
+    let mut out = String::new();
+    let mut i = 0;
+
+    while i < lines.len() {
+        // Find the run of lines that are aligned together.
+        let mut end = i + 1;
+        while end < lines.len() && lines[end].aligns_with(lines[i]) { end += 1; }
+
+        let run = &lines[i..end];
+        let widths = column_widths(run);
+
+        let codes: Vec<String> = run.iter().map(|line| code(line, &widths)).collect();
+        let code_width = codes.iter().map(|code| code.chars().count()).max().unwrap_or(0);
+
+        for (offset, (line, code)) in run.iter().zip(&codes).enumerate() {
+            let indent = if indents[i + offset] { INDENT } else { "" };
+
+            let text = match (line, line.comment()) {
+                (Line::Comment(comment), _) => comment.clone(),
+                (_, Some(comment)) if !code.is_empty() => format!("{code:code_width$} {comment}"),
+                _ => code.clone(),
+            };
+
+            if !text.is_empty() { out.push_str(indent); }
+            out.push_str(text.trim_end());
+            out.push('\n');
+        }
+
+        i = end;
+    }
+
+    out
+}
+
+/// Drop the leading and trailing blank lines, and collapse the repeated ones.
+fn lines_without_repeated_blanks(lines: &[Line]) -> Vec<&Line> {
+    let mut result: Vec<&Line> = vec![];
+
+    for line in lines {
+        let previous_blank = result.last().map_or(true, |last| **last == Line::Blank);
+        if *line == Line::Blank && previous_blank { continue; }
+
+        result.push(line);
+    }
+
+    while result.last() == Some(&&Line::Blank) { result.pop(); }
+
+    result
+}
+
+/// Should each line be indented?
+/// Instructions are indented under labels. Comments follow the indentation of the code after them.
+fn indents(lines: &[&Line]) -> Vec<bool> {
+    let mut in_label = false;
+
+    let mut indents: Vec<Option<bool>> = lines.iter().map(|line| match line {
+        Line::Label { .. } => {
+            in_label = true;
+            Some(false)
+        }
+        Line::Instruction { .. } => Some(in_label),
+        Line::Definition { .. } => Some(false),
+        Line::Blank | Line::Comment(_) => None,
+    }).collect();
+
+    let mut next = false;
+
+    for indent in indents.iter_mut().rev() {
+        match indent {
+            Some(value) => next = *value,
+            None => *indent = Some(next),
+        }
+    }
+
+    indents.into_iter().map(|indent| indent.unwrap_or(false)).collect()
+}
+
+/// Width of each column but the last, across the lines.
+fn column_widths(lines: &[&Line]) -> Vec<usize> {
+    let mut widths: Vec<usize> = vec![];
+
+    for parts in lines.iter().filter_map(|line| line.parts()) {
+        for (i, part) in parts.iter().enumerate().take(parts.len().saturating_sub(1)) {
+            let width = part.chars().count();
+
+            match widths.get_mut(i) {
+                Some(w) => *w = (*w).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+
+    widths
+}
+
+/// Render the code of the line without its comment.
+fn code(line: &Line, widths: &[usize]) -> String {
+    match line {
+        Line::Label { name, .. } => name.clone(),
+        Line::Instruction { parts, .. } | Line::Definition { parts, .. } => {
+            let mut code = String::new();
+
+            for (i, part) in parts.iter().enumerate() {
+                if i + 1 < parts.len() {
+                    let width = widths.get(i).copied().unwrap_or(0);
+                    code.push_str(&format!("{part:width$} "));
+                } else {
+                    code.push_str(part);
+                }
+            }
+
+            code
+        }
+        Line::Comment(_) | Line::Blank => String::new(),
+    }
+}
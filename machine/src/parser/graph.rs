@@ -0,0 +1,341 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt::Write;
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use crate::{Op, Parser};
+
+/// Sequence of operations that runs from the first to the last, without jumping in or out in between.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct BasicBlock {
+    pub id: usize,
+
+    /// Address of the first operation.
+    pub start: u16,
+
+    /// Address after the last operation.
+    pub end: u16,
+
+    /// Labels that point to the first operation.
+    pub labels: Vec<String>,
+
+    /// Operations in the assembly syntax, e.g. `push 5`
+    pub ops: Vec<String>,
+
+    /// Source line of each operation, counted from 0 as in the tokens.
+    pub lines: Vec<usize>,
+
+    /// Can the block run, starting from the program or the error handler?
+    pub reachable: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub enum EdgeKind {
+    /// Execution continues with the next block.
+    Next,
+
+    /// Unconditional `jump`.
+    Jump,
+
+    /// Conditional `jump_zero` or `jump_not_zero`, when the jump is taken.
+    Branch,
+
+    /// `call` to a subroutine. Its return continues with the next block.
+    Call,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Edge {
+    pub from: usize,
+    pub to: usize,
+    pub kind: EdgeKind,
+}
+
+/// The program, a subroutine or the error handler.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Function {
+    pub name: String,
+
+    /// Block where the function starts.
+    pub entry: usize,
+
+    /// Blocks that run as part of the function, excluding the functions it calls.
+    pub blocks: Vec<usize>,
+}
+
+/// A function calls another function.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct CallEdge {
+    pub caller: usize,
+    pub callee: usize,
+}
+
+/// Control-flow graph and call graph of the assembled operations.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct ControlFlowGraph {
+    pub blocks: Vec<BasicBlock>,
+    pub edges: Vec<Edge>,
+    pub functions: Vec<Function>,
+    pub calls: Vec<CallEdge>,
+}
+
+impl ControlFlowGraph {
+    /// Build the graph from the parsed operations, using the label offsets in the symbols.
+    pub fn new(parser: &Parser) -> ControlFlowGraph {
+        let ops = &parser.ops;
+
+        // Address of each operation, and the address after the last one.
+        let mut addresses = vec![];
+        let mut address: u16 = 0;
+
+        for op in ops {
+            addresses.push(address);
+            address = address.saturating_add(op.arity() as u16 + 1);
+        }
+
+        addresses.push(address);
+        let indices: HashMap<u16, usize> = addresses.iter().enumerate().map(|(i, a)| (*a, i)).collect();
+
+        // Labels at each code address.
+        let mut labels: BTreeMap<u16, Vec<String>> = BTreeMap::new();
+
+        for (key, offset) in &parser.symbols.offsets {
+            if parser.symbols.strings.contains_key(key) || parser.symbols.data.contains_key(key) { continue; }
+            labels.entry(*offset).or_default().push(key.clone());
+        }
+
+        labels.values_mut().for_each(|names| names.sort());
+
+        // Blocks start at the program start, the labels, the jump targets, and after the jumps.
+        let mut leaders = BTreeSet::from([0]);
+
+        for address in labels.keys() {
+            if let Some(index) = indices.get(address) { leaders.insert(*index); }
+        }
+
+        for (index, op) in ops.iter().enumerate() {
+            if let Some(target) = jump_target(op) {
+                if let Some(target) = indices.get(&target) { leaders.insert(*target); }
+            }
+
+            if ends_block(op) { leaders.insert(index + 1); }
+        }
+
+        let leaders: Vec<usize> = leaders.into_iter().filter(|index| *index < ops.len()).collect();
+
+        let mut blocks = vec![];
+        let mut block_of = HashMap::new();
+
+        for (id, start) in leaders.iter().enumerate() {
+            let end = leaders.get(id + 1).copied().unwrap_or(ops.len());
+            block_of.insert(addresses[*start], id);
+
+            blocks.push(BasicBlock {
+                id,
+                start: addresses[*start],
+                end: addresses[end],
+                labels: labels.get(&addresses[*start]).cloned().unwrap_or_default(),
+                ops: ops[*start..end].iter().map(|op| op_text(op, &labels)).collect(),
+                lines: (*start..end).map(|i| parser.lines.get(i).copied().unwrap_or(0)).collect(),
+                reachable: false,
+            });
+        }
+
+        // Connect the last operation of each block to the blocks that can run after it.
+        let mut edges = vec![];
+
+        for block in &blocks {
+            let Some(last) = leaders.get(block.id + 1).map_or(ops.len(), |i| *i).checked_sub(1) else { continue; };
+            let op = ops[last];
+            let next = block_of.get(&block.end).copied();
+            let target = jump_target(&op).and_then(|address| block_of.get(&address).copied());
+
+            let mut connect = |to: Option<usize>, kind| {
+                if let Some(to) = to { edges.push(Edge { from: block.id, to, kind }); }
+            };
+
+            match op {
+                Op::Jump(_) => connect(target, EdgeKind::Jump),
+                Op::JumpZero(_) | Op::JumpNotZero(_) => {
+                    connect(target, EdgeKind::Branch);
+                    connect(next, EdgeKind::Next);
+                }
+                Op::Call(_) => {
+                    connect(target, EdgeKind::Call);
+                    connect(next, EdgeKind::Next);
+                }
+                Op::Return | Op::Halt | Op::Eof => {}
+                _ => connect(next, EdgeKind::Next),
+            }
+        }
+
+        let mut graph = ControlFlowGraph { blocks, edges, functions: vec![], calls: vec![] };
+        let handler = parser.error_handler.and_then(|address| block_of.get(&address).copied());
+
+        graph.mark_reachable(handler);
+        graph.find_functions(handler);
+        graph
+    }
+
+    /// Mark the blocks that can run, starting from the program or the error handler.
+    fn mark_reachable(&mut self, handler: Option<usize>) {
+        if self.blocks.is_empty() { return; }
+
+        let entries = [Some(0), handler];
+
+        for id in self.walk(entries.iter().flatten().copied(), |_| true) {
+            self.blocks[id].reachable = true;
+        }
+    }
+
+    /// The program, the subroutines and the error handler each form a function.
+    fn find_functions(&mut self, handler: Option<usize>) {
+        if self.blocks.is_empty() { return; }
+
+        let mut entries = vec![0];
+        entries.extend(self.edges.iter().filter(|edge| edge.kind == EdgeKind::Call).map(|edge| edge.to));
+        entries.extend(handler);
+
+        let mut seen = BTreeSet::new();
+        entries.retain(|entry| seen.insert(*entry));
+
+        for entry in &entries {
+            let name = match self.blocks[*entry].labels.first() {
+                Some(label) => label.clone(),
+                None if *entry == 0 => "main".into(),
+                None => format!("0x{:04x}", self.blocks[*entry].start),
+            };
+
+            let mut blocks: Vec<usize> = self.walk([*entry], |kind| kind != EdgeKind::Call).into_iter().collect();
+            blocks.sort();
+
+            self.functions.push(Function { name, entry: *entry, blocks });
+        }
+
+        for (caller, function) in self.functions.iter().enumerate() {
+            for edge in &self.edges {
+                if edge.kind != EdgeKind::Call || !function.blocks.contains(&edge.from) { continue; }
+
+                let Some(callee) = entries.iter().position(|entry| *entry == edge.to) else { continue; };
+                let call = CallEdge { caller, callee };
+
+                if !self.calls.contains(&call) { self.calls.push(call); }
+            }
+        }
+    }
+
+    /// Blocks that can be reached from the entries, following the edges of the given kinds.
+    fn walk(&self, entries: impl IntoIterator<Item = usize>, follow: impl Fn(EdgeKind) -> bool) -> BTreeSet<usize> {
+        let mut seen = BTreeSet::new();
+        let mut queue: VecDeque<usize> = entries.into_iter().collect();
+
+        while let Some(id) = queue.pop_front() {
+            if !seen.insert(id) { continue; }
+
+            for edge in &self.edges {
+                if edge.from == id && follow(edge.kind) { queue.push_back(edge.to); }
+            }
+        }
+
+        seen
+    }
+
+    /// Render the control-flow graph in the Graphviz DOT format.
+    /// Unreachable blocks are drawn with dashed borders.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph program {\n    node [shape=box, fontname=\"monospace\"];\n\n");
+
+        for block in &self.blocks {
+            let mut label = String::new();
+
+            for name in &block.labels {
+                let _ = write!(label, "{}:\\l", escape(name));
+            }
+
+            for op in &block.ops {
+                let _ = write!(label, "    {}\\l", escape(op));
+            }
+
+            let style = if block.reachable { "" } else { ", style=dashed, fontcolor=gray" };
+            let _ = writeln!(dot, "    b{} [label=\"{}\"{}];", block.id, label, style);
+        }
+
+        dot.push('\n');
+
+        for edge in &self.edges {
+            let attributes = match edge.kind {
+                EdgeKind::Next => "",
+                EdgeKind::Jump => " [label=\"jump\"]",
+                EdgeKind::Branch => " [label=\"branch\"]",
+                EdgeKind::Call => " [label=\"call\", style=dashed]",
+            };
+
+            let _ = writeln!(dot, "    b{} -> b{}{};", edge.from, edge.to, attributes);
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Render the call graph in the Graphviz DOT format.
+    pub fn to_call_graph_dot(&self) -> String {
+        let mut dot = String::from("digraph calls {\n    node [shape=box, fontname=\"monospace\"];\n\n");
+
+        for (id, function) in self.functions.iter().enumerate() {
+            let _ = writeln!(dot, "    f{} [label=\"{}\"];", id, escape(&function.name));
+        }
+
+        dot.push('\n');
+
+        for call in &self.calls {
+            let _ = writeln!(dot, "    f{} -> f{};", call.caller, call.callee);
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl From<&Parser> for ControlFlowGraph {
+    fn from(parser: &Parser) -> Self {
+        ControlFlowGraph::new(parser)
+    }
+}
+
+/// Address that the operation may jump or call to.
+fn jump_target(op: &Op) -> Option<u16> {
+    match op {
+        Op::Jump(address) | Op::JumpZero(address) | Op::JumpNotZero(address) | Op::Call(address) => Some(*address),
+        _ => None,
+    }
+}
+
+/// Does the operation end a basic block?
+fn ends_block(op: &Op) -> bool {
+    jump_target(op).is_some() || matches!(op, Op::Return | Op::Halt | Op::Eof)
+}
+
+/// Render the operation in the assembly syntax, with the label names as jump targets.
+fn op_text(op: &Op, labels: &BTreeMap<u16, Vec<String>>) -> String {
+    if let Some(name) = jump_target(op).and_then(|address| labels.get(&address)).and_then(|names| names.first()) {
+        return format!("{op} {name}");
+    }
+
+    let mut text = op.to_string();
+
+    for value in op.field_values() {
+        let _ = write!(text, " {value}");
+    }
+
+    text
+}
+
+/// Escape the text for a quoted DOT string.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -2,17 +2,26 @@ pub mod token;
 pub mod scanner;
 pub mod symbols;
 pub mod parse_error;
+pub mod verify;
+pub mod optimize;
+pub mod graph;
+pub mod formatter;
 
 pub use token::*;
 pub use scanner::*;
 pub use symbols::*;
 pub use parse_error::*;
+pub use verify::{verify, StackWarning};
+pub use optimize::Optimizer;
+pub use graph::*;
+pub use formatter::format_source;
 
 use std::str::FromStr;
 use snafu::ensure;
 use TokenType as T;
-use crate::{DATA_START, Op};
-use crate::ParseError::{CannotPeekAtToken, InvalidArgToken, InvalidByteValue, InvalidIdentifier, InvalidLabelDescription, InvalidStringValue, UndefinedInstruction, UndefinedSymbols};
+use crate::Op;
+use crate::mem::MemoryLayout;
+use crate::ParseError::{CannotPeekAtToken, DataSegmentOverflow, InvalidArgToken, InvalidByteValue, InvalidIdentifier, InvalidLabelDescription, InvalidStringValue, UndefinedInstruction, UndefinedSymbols};
 
 type Errorable = Result<(), ParseError>;
 
@@ -27,9 +36,18 @@ pub struct Parser {
     /// Output a set of operations.
     pub ops: Vec<Op>,
 
+    /// Source line of each operation.
+    pub lines: Vec<usize>,
+
+    /// Arguments that refer to labels, as `(operation index, argument position)`.
+    pub label_args: Vec<(usize, usize)>,
+
     /// Output a set of symbols.
     pub symbols: Symbols,
 
+    /// Address of the error handler label, defined by `.on_error`.
+    pub error_handler: Option<u16>,
+
     /// Is the first pass of symbol scanning completed?
     symbol_scanned: bool,
 
@@ -41,6 +59,15 @@ pub struct Parser {
 
     /// Current data offsets
     data_offset: u16,
+
+    /// Position of the argument being parsed.
+    arg_position: usize,
+
+    /// Memory layout of the machine the program is loaded into.
+    pub layout: MemoryLayout,
+
+    /// Should the operations be optimized after parsing?
+    pub optimize: bool,
 }
 
 impl Parser {
@@ -49,15 +76,26 @@ impl Parser {
             source: source.into(),
             tokens: vec![],
             ops: vec![],
+            lines: vec![],
+            label_args: vec![],
             symbols: Symbols::new(),
+            error_handler: None,
             symbol_scanned: false,
 
             current: 0,
             code_offset: 0,
             data_offset: 0,
+            arg_position: 0,
+
+            layout: MemoryLayout::default(),
+            optimize: false,
         }
     }
 
+    pub fn with_layout(source: &str, layout: MemoryLayout) -> Parser {
+        Parser { layout, ..Parser::new(source) }
+    }
+
     pub fn parse(&mut self) -> Errorable {
         // Scan tokens from the source code.
         let mut scanner = Scanner::new(&self.source);
@@ -73,15 +111,43 @@ impl Parser {
         // Pass 2: collect op with memory offsets in labels.
         self.parse_tokens()?;
 
+        // The program must fit in the memory layout.
+        let (code, data) = (self.layout.code_size, self.layout.data_size);
+        ensure!(self.code_offset <= code, CodeSegmentOverflowSnafu { size: self.code_offset, limit: code });
+        ensure!(self.data_offset <= data, DataSegmentOverflowSnafu { size: self.data_offset, limit: data });
+
+        if self.optimize {
+            self.run_optimizer();
+        }
+
         Ok(())
     }
 
+    /// Tokens scanned from the source.
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// Token where the parser stopped, e.g. the token that caused a parse error.
+    pub fn current_token(&self) -> Option<&Token> {
+        self.tokens.get(self.current)
+    }
+
+    /// Verify the stack usage of the parsed program.
+    /// Warnings do not prevent the program from running.
+    pub fn verify(&self) -> Vec<StackWarning> {
+        verify(&self.ops, &self.lines, self.error_handler)
+    }
+
     pub fn parse_tokens(&mut self) -> Errorable {
         // Reset the parser state.
         self.current = 0;
         self.code_offset = 0;
         self.data_offset = 0;
         self.ops.clear();
+        self.lines.clear();
+        self.label_args.clear();
+        self.error_handler = None;
 
         // Parse each token.
         while self.current < self.tokens.len() {
@@ -103,6 +169,7 @@ impl Parser {
             T::Instruction => self.save_instruction(token)?,
             T::StringDefinition => self.save_string()?,
             T::ValueDefinition => self.save_value()?,
+            T::ErrorHandlerDefinition => self.save_error_handler()?,
             T::Identifier => {}
             T::String(..) => {}
             T::Value(..) => {}
@@ -114,13 +181,12 @@ impl Parser {
         Ok(())
     }
 
-    fn advance(&mut self) {
-        if self.current >= self.tokens.len() - 1 {
-            println!("cannot advance! {} >= {}", self.current, self.tokens.len());
-            return;
-        }
+    /// Move to the next token. The program must not end in the middle of an instruction or definition.
+    fn advance(&mut self) -> Errorable {
+        ensure!(self.current + 1 < self.tokens.len(), UnexpectedEndOfProgramSnafu);
 
         self.current += 1;
+        Ok(())
     }
 
     fn save_label(&mut self, token: &Token) -> Errorable {
@@ -175,7 +241,7 @@ impl Parser {
         // Do not process if the symbol is already scanned in the first pass.
         if self.symbol_scanned { return Ok(None); }
 
-        self.advance();
+        self.advance()?;
         let key = self.identifier_name()?;
 
         // The same symbol is defined twice.
@@ -183,8 +249,8 @@ impl Parser {
 
         self.symbols.offsets.insert(key.clone(), self.data_offset);
 
-        self.advance();
-        self.data_offset += 1;
+        self.advance()?;
+        self.data_offset = self.data_offset.saturating_add(1);
 
         Ok(Some(key))
     }
@@ -198,8 +264,8 @@ impl Parser {
         ensure!(!self.symbols.strings.contains_key(&key), DuplicateStringDefinitionSnafu);
 
         let value = self.string_value()?;
-        let len = value.len() as u16;
-        self.data_offset += len;
+        let len = u16::try_from(value.encode_utf16().count()).unwrap_or(u16::MAX);
+        self.data_offset = self.data_offset.saturating_add(len);
 
         self.symbols.strings.insert(key.clone(), value);
 
@@ -212,7 +278,25 @@ impl Parser {
         };
 
         self.symbols.data.insert(key.clone(), vec![self.byte_value()?]);
-        self.data_offset += 1;
+        self.data_offset = self.data_offset.saturating_add(1);
+
+        Ok(())
+    }
+
+    fn save_error_handler(&mut self) -> Errorable {
+        self.advance()?;
+        let key = self.identifier_name()?;
+
+        // Labels may be defined after the handler, so we resolve them after the first pass.
+        if !self.symbol_scanned { return Ok(()); }
+
+        ensure!(self.error_handler.is_none(), DuplicateErrorHandlerSnafu);
+
+        // The error handler must be a label in the code segment.
+        let is_data = self.symbols.strings.contains_key(&key) || self.symbols.data.contains_key(&key);
+        let offset = self.symbols.offsets.get(&key).filter(|_| !is_data).ok_or(UndefinedSymbols)?;
+
+        self.error_handler = Some(*offset);
 
         Ok(())
     }
@@ -227,13 +311,16 @@ impl Parser {
         if op == Op::Noop { return Ok(()); }
 
         self.ops.push(op);
-        self.code_offset += arity + 1;
+        self.lines.push(token.line);
+        // Oversized programs are reported once parsing completes.
+        self.code_offset = self.code_offset.saturating_add(arity + 1);
 
         Ok(())
     }
 
     fn instruction(&mut self, op_str: &str) -> Result<Op, ParseError> {
         let mut errors: Vec<ParseError> = vec![];
+        self.arg_position = 0;
 
         let arg_fn = || {
             self.arg().unwrap_or_else(|err| {
@@ -250,7 +337,8 @@ impl Parser {
     }
 
     fn arg(&mut self) -> Result<u16, ParseError> {
-        self.advance();
+        self.advance()?;
+        self.arg_position += 1;
 
         let token = self.peek()?;
 
@@ -271,7 +359,8 @@ impl Parser {
 
         // Strings should be loaded from the data segment.
         if self.symbols.strings.contains_key(key) {
-            return Ok(DATA_START + *offset);
+            let limit = u16::MAX - self.layout.data_start();
+            return self.layout.data_start().checked_add(*offset).ok_or(DataSegmentOverflow { size: *offset, limit });
         }
 
         // Raw bytes are loaded directly into the code segment.
@@ -282,6 +371,7 @@ impl Parser {
         }
 
         // Labels stores the offsets within the code segment.
+        self.label_args.push((self.ops.len(), self.arg_position - 1));
         Ok(*offset)
     }
 }
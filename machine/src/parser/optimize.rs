@@ -0,0 +1,306 @@
+use std::collections::{BTreeSet, HashMap};
+use crate::{Op, Parser};
+
+/// An operation, along with where it came from.
+#[derive(Debug, Clone)]
+struct Slot {
+    op: Op,
+
+    /// Address of the operation before optimization.
+    address: u16,
+
+    /// Source line of the operation.
+    line: usize,
+
+    /// Positions of the arguments that hold code addresses.
+    relocations: Vec<usize>,
+}
+
+/// Peephole optimizer for the parsed operations.
+///
+/// Operations that are the target of a jump, a call or a label are never merged into the previous operation,
+/// so every address that can be jumped to still exists after the optimization.
+/// The optimized program computes the same results, but runs fewer instructions.
+pub struct Optimizer {
+    slots: Vec<Slot>,
+
+    /// Addresses that may be jumped to, before optimization.
+    targets: BTreeSet<u16>,
+
+    /// Addresses of every operation and the end of the code, before optimization.
+    starts: Vec<u16>,
+}
+
+impl Optimizer {
+    /// Addresses in the arguments of jumps and calls are always relocated.
+    /// The label arguments are the `(operation index, argument position)` of other arguments that refer to labels.
+    pub fn new(ops: &[Op], lines: &[usize], label_args: &[(usize, usize)]) -> Optimizer {
+        let mut slots = vec![];
+        let mut address: u16 = 0;
+
+        for (index, op) in ops.iter().enumerate() {
+            let mut relocations: Vec<usize> = label_args.iter()
+                .filter(|(i, _)| *i == index)
+                .map(|(_, position)| *position)
+                .collect();
+
+            if let Op::Jump(_) | Op::JumpZero(_) | Op::JumpNotZero(_) | Op::Call(_) = op {
+                if !relocations.contains(&0) { relocations.push(0); }
+            }
+
+            slots.push(Slot { op: *op, address, line: lines.get(index).copied().unwrap_or(0), relocations });
+            address = address.saturating_add(op.arity() as u16 + 1);
+        }
+
+        let mut starts: Vec<u16> = slots.iter().map(|slot| slot.address).collect();
+        starts.push(address);
+
+        let mut targets = BTreeSet::new();
+
+        for slot in &slots {
+            for position in &slot.relocations {
+                targets.insert(slot.op.field_values()[*position]);
+            }
+        }
+
+        Optimizer { slots, targets, starts }
+    }
+
+    /// Mark the address as reachable from outside the code, e.g. a label or the error handler.
+    pub fn add_target(&mut self, address: u16) {
+        self.targets.insert(address);
+    }
+
+    /// Apply the optimizations until none of them applies.
+    /// Returns the optimized operations, their source lines, and the new address of every old address.
+    pub fn optimize(mut self) -> (Vec<Op>, Vec<usize>, HashMap<u16, u16>) {
+        while self.fold_constants() | self.remove_pairs() | self.remove_jumps_to_next() | self.remove_dead_code() {}
+
+        let relocation = self.relocation();
+
+        let ops = self.slots.iter().map(|slot| {
+            let values = slot.op.field_values();
+            let mut position = 0;
+
+            slot.op.with_arg(|| {
+                let value = values[position];
+                let relocate = slot.relocations.contains(&position);
+                position += 1;
+
+                if relocate { *relocation.get(&value).unwrap_or(&value) } else { value }
+            })
+        }).collect();
+
+        let lines = self.slots.iter().map(|slot| slot.line).collect();
+
+        (ops, lines, relocation)
+    }
+
+    /// Can the operation at the index be merged into the operation before it?
+    /// It cannot if a jump lands on it, including jumps to the operations removed before it.
+    fn is_mergeable(&self, index: usize) -> bool {
+        let Some(slot) = self.slots.get(index) else { return false; };
+
+        match index.checked_sub(1).map(|i| self.slots[i].address) {
+            Some(previous) => self.targets.range(previous + 1..=slot.address).next().is_none(),
+            None => self.targets.range(..=slot.address).next().is_none(),
+        }
+    }
+
+    /// Does the operation at the index push a constant that is not an address?
+    fn constant(&self, index: usize) -> Option<u16> {
+        match self.slots.get(index) {
+            Some(Slot { op: Op::Push(value), relocations, .. }) if relocations.is_empty() => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Replace the pushes of constants and the operation on them with a push of the result.
+    /// `push 2; push 3; add` becomes `push 5`. Operations that would fail at runtime are kept as is.
+    fn fold_constants(&mut self) -> bool {
+        let mut changed = false;
+        let mut i = 0;
+
+        while i < self.slots.len() {
+            let Some(a) = self.constant(i) else { i += 1; continue; };
+
+            // Operations on a single value, e.g. `push 2; inc`
+            if self.is_mergeable(i + 1) {
+                if let Some(value) = fold_one(self.slots[i + 1].op, a) {
+                    self.slots[i].op = Op::Push(value);
+                    self.slots.remove(i + 1);
+                    changed = true;
+                    continue;
+                }
+            }
+
+            // Operations on two values, e.g. `push 2; push 3; add`
+            if let Some(b) = self.constant(i + 1) {
+                if self.is_mergeable(i + 1) && self.is_mergeable(i + 2) {
+                    if let Some(value) = fold_two(self.slots[i + 2].op, a, b) {
+                        self.slots[i].op = Op::Push(value);
+                        self.slots.drain(i + 1..=i + 2);
+                        changed = true;
+                        continue;
+                    }
+                }
+            }
+
+            i += 1;
+        }
+
+        changed
+    }
+
+    /// Remove the values that are popped right after they are pushed: `push 1; pop` and `dup; pop`.
+    fn remove_pairs(&mut self) -> bool {
+        let mut changed = false;
+        let mut i = 0;
+
+        while i + 1 < self.slots.len() {
+            let first = self.slots[i].op;
+            let pushes_one = matches!(first, Op::Push(_) | Op::Dup);
+
+            if pushes_one && self.slots[i + 1].op == Op::Pop && self.is_mergeable(i + 1) {
+                self.slots.drain(i..=i + 1);
+                changed = true;
+                continue;
+            }
+
+            i += 1;
+        }
+
+        changed
+    }
+
+    /// Remove the jumps to the operation right after them.
+    fn remove_jumps_to_next(&mut self) -> bool {
+        let mut changed = false;
+        let mut i = 0;
+
+        while i < self.slots.len() {
+            if let Op::Jump(address) = self.slots[i].op {
+                if self.is_start(address) && self.resolve(address) == i + 1 {
+                    self.slots.remove(i);
+                    changed = true;
+                    continue;
+                }
+            }
+
+            i += 1;
+        }
+
+        changed
+    }
+
+    /// Remove the operations after `halt`, `jump` and `return` that cannot be jumped to.
+    fn remove_dead_code(&mut self) -> bool {
+        let mut changed = false;
+        let mut i = 0;
+
+        while i < self.slots.len() {
+            if let Op::Halt | Op::Jump(_) | Op::Return = self.slots[i].op {
+                while self.is_mergeable(i + 1) {
+                    self.slots.remove(i + 1);
+                    changed = true;
+                }
+            }
+
+            i += 1;
+        }
+
+        changed
+    }
+
+    /// Was the address the start of an operation, or the end of the code?
+    fn is_start(&self, address: u16) -> bool {
+        self.starts.binary_search(&address).is_ok()
+    }
+
+    /// Index of the operation that now runs at the old address.
+    /// Removed operations are replaced by the operation after them.
+    fn resolve(&self, address: u16) -> usize {
+        self.slots.partition_point(|slot| slot.address < address)
+    }
+
+    /// New address of every old address that was the start of an operation.
+    fn relocation(&self) -> HashMap<u16, u16> {
+        let mut new_addresses = vec![];
+        let mut address: u16 = 0;
+
+        for slot in &self.slots {
+            new_addresses.push(address);
+            address = address.saturating_add(slot.op.arity() as u16 + 1);
+        }
+
+        // The end of the code is where the end-of-file marker is placed.
+        new_addresses.push(address);
+
+        self.starts.iter().map(|address| (*address, new_addresses[self.resolve(*address)])).collect()
+    }
+}
+
+/// Result of an operation on a single constant, if it cannot fail.
+fn fold_one(op: Op, a: u16) -> Option<u16> {
+    match op {
+        Op::Inc => a.checked_add(1),
+        Op::Dec => Some(a.saturating_sub(1)),
+        Op::Not => Some(!a),
+        _ => None,
+    }
+}
+
+/// Result of an operation on two constants, if it cannot fail.
+fn fold_two(op: Op, a: u16, b: u16) -> Option<u16> {
+    match op {
+        Op::Add => a.checked_add(b),
+        Op::Sub => a.checked_sub(b),
+        Op::Mul => a.checked_mul(b),
+        Op::Div => a.checked_div(b),
+        Op::Mod => a.checked_rem(b),
+        Op::And => Some(a & b),
+        Op::Or => Some(a | b),
+        Op::Xor => Some(a ^ b),
+        Op::LeftShift => a.checked_shl(b as u32),
+        Op::RightShift => a.checked_shr(b as u32),
+        Op::Equal => Some((a == b).into()),
+        Op::NotEqual => Some((a != b).into()),
+        Op::LessThan => Some((a < b).into()),
+        Op::LessThanOrEqual => Some((a <= b).into()),
+        Op::GreaterThan => Some((a > b).into()),
+        Op::GreaterThanOrEqual => Some((a >= b).into()),
+        _ => None,
+    }
+}
+
+impl Parser {
+    /// Optimize the parsed operations, and relocate the labels and the error handler.
+    pub fn run_optimizer(&mut self) {
+        let mut optimizer = Optimizer::new(&self.ops, &self.lines, &self.label_args);
+
+        let labels: Vec<String> = self.symbols.offsets.keys()
+            .filter(|key| !self.symbols.strings.contains_key(*key) && !self.symbols.data.contains_key(*key))
+            .cloned()
+            .collect();
+
+        for label in &labels {
+            optimizer.add_target(self.symbols.offsets[label]);
+        }
+
+        if let Some(handler) = self.error_handler {
+            optimizer.add_target(handler);
+        }
+
+        let (ops, lines, relocation) = optimizer.optimize();
+
+        for label in &labels {
+            let offset = self.symbols.offsets[label];
+            self.symbols.offsets.insert(label.clone(), *relocation.get(&offset).unwrap_or(&offset));
+        }
+
+        self.error_handler = self.error_handler.map(|handler| *relocation.get(&handler).unwrap_or(&handler));
+        self.ops = ops;
+        self.lines = lines;
+        self.label_args.clear();
+    }
+}
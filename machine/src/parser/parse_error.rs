@@ -31,6 +31,9 @@ pub enum ParseError {
     #[snafu(display("duplicate symbol definition"))]
     DuplicateSymbolDefinition,
 
+    #[snafu(display("error handler is defined more than once"))]
+    DuplicateErrorHandler,
+
     #[snafu(display("invalid argument"))]
     InvalidArgument { errors: Vec<ParseError> },
 
@@ -58,6 +61,18 @@ pub enum ParseError {
     #[snafu(display("scanner reached end of line without terminating"))]
     ScannerReachedEndOfLine,
 
+    #[snafu(display("program ends in the middle of an instruction or definition"))]
+    UnexpectedEndOfProgram,
+
+    #[snafu(display("invalid binary digit"))]
+    InvalidBinaryDigit { text: String },
+
     #[snafu(display("program does not contain any instructions to run"))]
     EmptyProgram,
+
+    #[snafu(display("code is {size} words long, but the code segment only holds {limit} words"))]
+    CodeSegmentOverflow { size: u16, limit: u16 },
+
+    #[snafu(display("data is {size} words long, but the data segment only holds {limit} words"))]
+    DataSegmentOverflow { size: u16, limit: u16 },
 }
@@ -1,19 +1,32 @@
+use std::str::Chars;
 use snafu::ensure;
 use crate::{ParseError, ScannerReachedEndOfLineSnafu};
-use crate::ParseError::{InvalidDecimalDigit, InvalidHexDigit, PeekExceedsSourceLength};
+use crate::ParseError::{InvalidBinaryDigit, InvalidDecimalDigit, InvalidHexDigit, PeekExceedsSourceLength};
 use super::token::*;
 
 type Errorable = Result<(), ParseError>;
 
+/// Scans the source into tokens in a single pass.
+/// Positions are byte offsets into the source, so they always fall on a character boundary.
 #[derive(Debug)]
 pub struct Scanner {
     pub source: String,
     pub tokens: Vec<Token>,
 
+    /// Byte offset of the start of the current token.
     pub start: usize,
+
+    /// Byte offset of the next character.
     pub current: usize,
+
     pub line: usize,
 
+    /// Column of the next character, counted in characters from the start of the line.
+    pub column: usize,
+
+    /// Column of the start of the current token.
+    pub start_column: usize,
+
     pub in_instruction: bool,
     pub in_definition: bool,
 }
@@ -27,6 +40,8 @@ impl Scanner {
             start: 0,
             current: 0,
             line: 0,
+            column: 0,
+            start_column: 0,
 
             in_instruction: false,
             in_definition: false,
@@ -36,6 +51,7 @@ impl Scanner {
     pub fn scan_tokens(&mut self) -> Errorable {
         while !self.is_end() {
             self.start = self.current;
+            self.start_column = self.column;
             self.scan_token()?;
         }
 
@@ -45,13 +61,18 @@ impl Scanner {
     fn peek(&self) -> Result<char, ParseError> {
         if self.is_end() { return Ok('\0'); }
 
-        self.source.chars().nth(self.current).ok_or(PeekExceedsSourceLength)
+        self.rest().next().ok_or(PeekExceedsSourceLength)
     }
 
     fn peek_next(&self) -> Result<char, ParseError> {
         if self.is_end() { return Ok('\0'); }
 
-        self.source.chars().nth(self.current + 1).ok_or(PeekExceedsSourceLength)
+        Ok(self.rest().nth(1).unwrap_or('\0'))
+    }
+
+    /// Characters that are yet to be scanned.
+    fn rest(&self) -> Chars<'_> {
+        self.source[self.current..].chars()
     }
 
     fn is_end(&self) -> bool {
@@ -61,9 +82,16 @@ impl Scanner {
     fn advance(&mut self) -> Result<char, ParseError> {
         ensure!(!self.is_end(), ScannerReachedEndOfLineSnafu);
 
-        let v = self.peek();
-        self.current += 1;
-        v
+        let c = self.peek()?;
+        self.current += c.len_utf8();
+
+        if c == '\n' {
+            self.column = 0;
+        } else {
+            self.column += 1;
+        }
+
+        Ok(c)
     }
 
     fn newline(&mut self) {
@@ -103,6 +131,7 @@ impl Scanner {
                 let token = match &*text {
                     ".string" => Some(TokenType::StringDefinition),
                     ".value" => Some(TokenType::ValueDefinition),
+                    ".on_error" => Some(TokenType::ErrorHandlerDefinition),
                     _ => None
                 };
 
@@ -185,8 +214,11 @@ impl Scanner {
         }
 
         let text = self.peek_lexeme();
-        let hex_str = text.strip_prefix("0b").expect("no binary prefix");
-        let num = u16::from_str_radix(hex_str, 2).expect("invalid binary");
+        let text = text.trim();
+
+        let binary_str = text.strip_prefix("0b").ok_or(InvalidBinaryDigit { text: text.into() })?;
+        let num = u16::from_str_radix(binary_str, 2).map_err(|_| InvalidBinaryDigit { text: text.into() })?;
+
         self.add_token(TokenType::Value(num));
 
         Ok(())
@@ -245,6 +277,8 @@ impl Scanner {
             token_type: t,
             lexeme: self.peek_lexeme(),
             line: self.line,
+            column: self.start_column,
+            offset: self.start,
         });
     }
 
@@ -285,4 +319,28 @@ mod tests {
         assert_eq!(s.tokens[1].token_type, TokenType::Value(0));
         assert_eq!(s.tokens[2].token_type, TokenType::Value(1));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_utf8_comments_and_strings() {
+        let s: Scanner = "; héllo wörld ✨\n.string s \"こんにちは\" ; 🎉\npush 1".try_into().expect("cannot scan utf-8");
+        let types: Vec<TokenType> = s.tokens.iter().map(|t| t.token_type.clone()).collect();
+
+        assert_eq!(types, [
+            TokenType::StringDefinition,
+            TokenType::Identifier,
+            TokenType::String("こんにちは".into()),
+            TokenType::Instruction,
+            TokenType::Value(1),
+        ]);
+    }
+
+    #[test]
+    fn test_offsets_and_columns() {
+        let s: Scanner = "; ✨\n  push 0x1F\n\"é\" ok".try_into().expect("cannot scan");
+        let positions: Vec<(usize, usize, usize)> = s.tokens.iter().map(|t| (t.line, t.column, t.offset)).collect();
+
+        // The sparkle takes three bytes but a single column.
+        assert_eq!(positions, [(1, 2, 8), (1, 7, 13), (2, 0, 18), (2, 4, 23)]);
+        assert_eq!(&s.source[18..22], "\"é\"");
+    }
+}
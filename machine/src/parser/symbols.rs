@@ -23,7 +23,7 @@ impl Symbols {
         }
     }
 
-    // TODO: add unit tests for this method, as this is a source for regression bugs.
+    /// Lay out the strings and values at their offsets in the data segment.
     pub fn bytes(&self) -> Vec<u16> {
         // We must sort the offset table by their offset,
         // otherwise the binary will be out of order.
@@ -33,15 +33,13 @@ impl Symbols {
         let mut data: Vec<u16> = vec![];
 
         let mut write = |offset: usize, bytes: Vec<u16>| {
-            let capacity = offset + bytes.len();
+            let end = offset + bytes.len();
 
-            if data.len() <= capacity {
-                data.resize(capacity + 1, 0);
+            if data.len() < end {
+                data.resize(end, 0);
             }
 
-            for (i, byte) in bytes.iter().enumerate() {
-                data.insert(offset + i, *byte);
-            }
+            data[offset..end].copy_from_slice(&bytes);
         };
 
         for (key, offset) in offsets.iter() {
@@ -12,6 +12,9 @@ pub enum TokenType {
     /// Value definition keyword: ".value"
     ValueDefinition,
 
+    /// Error handler definition keyword: ".on_error"
+    ErrorHandlerDefinition,
+
     /// Instruction starts a line, such as "push"
     Instruction,
 
@@ -30,6 +33,12 @@ pub struct Token {
     pub token_type: TokenType,
     pub lexeme: String,
     pub line: usize,
+
+    /// Column of the first character, counted in characters from the start of the line.
+    pub column: usize,
+
+    /// Byte offset of the token in the source.
+    pub offset: usize,
 }
 
 pub fn is_identifier(c: char) -> bool {
@@ -0,0 +1,296 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use snafu::prelude::*;
+use tsify::Tsify;
+use crate::Op;
+
+/// Problems with the stack usage that are found before the program runs.
+/// Lines are counted from 1.
+#[derive(Debug, Snafu, Serialize, Deserialize, PartialEq, Clone, Tsify)]
+#[snafu(visibility(pub))]
+#[serde(tag = "type")]
+#[tsify(into_wasm_abi, from_wasm_abi, namespace)]
+pub enum StackWarning {
+    #[snafu(display("line {line}: '{op}' needs {pops} values, but the stack only has {depth}"))]
+    StackUnderflow { line: usize, op: String, depth: u32, pops: u32 },
+
+    #[snafu(display("line {line}: the loop changes the stack depth by {delta} on every iteration"))]
+    UnbalancedLoop { line: usize, delta: i32 },
+
+    #[snafu(display("line {line}: return leaves {actual} values on the stack, but another return of the subroutine leaves {expected}"))]
+    MismatchedReturnDepth { line: usize, expected: i32, actual: i32 },
+
+    #[snafu(display("line {line}: return is reached without a call"))]
+    ReturnWithoutCall { line: usize },
+}
+
+impl StackWarning {
+    pub fn line(&self) -> usize {
+        match self {
+            StackWarning::StackUnderflow { line, .. } => *line,
+            StackWarning::UnbalancedLoop { line, .. } => *line,
+            StackWarning::MismatchedReturnDepth { line, .. } => *line,
+            StackWarning::ReturnWithoutCall { line } => *line,
+        }
+    }
+}
+
+/// How a subroutine changes the stack, relative to the depth at the call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Summary {
+    /// The subroutine is still being analyzed, e.g. a recursive call.
+    InProgress,
+
+    /// The effect depends on runtime values.
+    Unknown,
+
+    /// The subroutine never returns.
+    NoReturn,
+
+    /// The subroutine needs `required` values on the stack, and changes the depth by `delta`.
+    Returns { required: i32, delta: i32 },
+}
+
+/// A jump from an operation to another, by index.
+type Edge = (usize, usize);
+
+/// The range of stack depths over the paths that reach an operation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Depths {
+    min: i32,
+    max: i32,
+}
+
+impl Depths {
+    fn merge(self, other: Depths) -> Depths {
+        Depths { min: self.min.min(other.min), max: self.max.max(other.max) }
+    }
+}
+
+/// Where the analysis of a code path starts.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Entry {
+    /// The start of the program. The stack is empty, so the depth is exact.
+    Program,
+
+    /// A subroutine or the error handler. The depth is relative to the caller's.
+    Subroutine,
+}
+
+/// Verifies the stack usage of every path through the control-flow graph.
+/// Paths through operations with a dynamic stack effect, such as `receive`, are not followed.
+struct Verifier<'a> {
+    ops: &'a [Op],
+
+    /// Source line of each operation.
+    lines: &'a [usize],
+
+    /// Index of the operation at each code address.
+    indices: HashMap<u16, usize>,
+
+    /// Address of the operation after each operation.
+    next: Vec<u16>,
+
+    summaries: HashMap<usize, Summary>,
+    warnings: Vec<StackWarning>,
+}
+
+/// Verify the stack usage of the operations.
+pub fn verify(ops: &[Op], lines: &[usize], error_handler: Option<u16>) -> Vec<StackWarning> {
+    let mut verifier = Verifier::new(ops, lines);
+    verifier.analyze(Entry::Program, 0, 0);
+
+    if let Some(&handler) = error_handler.and_then(|address| verifier.indices.get(&address)) {
+        // The handler is invoked with the error code on the stack.
+        verifier.summary(handler, 1);
+    }
+
+    verifier.warnings
+}
+
+impl<'a> Verifier<'a> {
+    fn new(ops: &'a [Op], lines: &'a [usize]) -> Verifier<'a> {
+        let mut indices = HashMap::new();
+        let mut next = vec![];
+        let mut address: u16 = 0;
+
+        for (index, op) in ops.iter().enumerate() {
+            indices.insert(address, index);
+            address = address.saturating_add(op.arity() as u16 + 1);
+            next.push(address);
+        }
+
+        Verifier { ops, lines, indices, next, summaries: HashMap::new(), warnings: vec![] }
+    }
+
+    fn line(&self, index: usize) -> usize {
+        self.lines.get(index).map_or(0, |line| line + 1)
+    }
+
+    fn warn(&mut self, warning: StackWarning) {
+        if !self.warnings.contains(&warning) {
+            self.warnings.push(warning);
+        }
+    }
+
+    /// Summarize the subroutine starting at the operation.
+    fn summary(&mut self, start: usize, depth: i32) -> Summary {
+        if let Some(summary) = self.summaries.get(&start) {
+            return *summary;
+        }
+
+        self.summaries.insert(start, Summary::InProgress);
+        let summary = self.analyze(Entry::Subroutine, start, depth);
+        self.summaries.insert(start, summary);
+
+        summary
+    }
+
+    /// Walk every path from the operation, tracking the range of stack depths before each operation.
+    fn analyze(&mut self, entry: Entry, start: usize, depth: i32) -> Summary {
+        let mut known = true;
+        let (order, back_edges) = self.order(start, &mut known);
+        let mut depths = HashMap::from([(start, Depths { min: depth, max: depth })]);
+
+        // Lowest depth reached by the subroutine, and the depth at its first return.
+        let mut lowest = 0;
+        let mut returned: Option<i32> = None;
+
+        for (index, successors) in order {
+            let Some(&Depths { min, max }) = depths.get(&index) else { continue; };
+            let Some(op) = self.ops.get(index).copied() else { continue; };
+            let line = self.line(index);
+
+            // A call is treated as an operation with the effect of the subroutine.
+            let (pops, pushes) = match op {
+                Op::Call(address) => match self.indices.get(&address).copied().map(|target| self.summary(target, 0)) {
+                    Some(Summary::Returns { required, delta }) => (required, required + delta),
+                    _ => continue,
+                },
+                _ => match op.stack_effect() {
+                    Some(effect) => (effect.pops as i32, effect.pushes as i32),
+                    None => continue,
+                },
+            };
+
+            // The underflow is only guaranteed if no path brings enough values.
+            if entry == Entry::Program && max < pops {
+                self.warn(StackWarning::StackUnderflow { line, op: op.to_string(), depth: max as u32, pops: pops as u32 });
+                continue;
+            }
+
+            // The paths that are short of values fault, so only the others continue.
+            let min = if entry == Entry::Program { min.max(pops) } else { min };
+            lowest = lowest.min(min - pops);
+            let after = Depths { min: min - pops + pushes, max: max - pops + pushes };
+
+            if op == Op::Return {
+                if entry == Entry::Program {
+                    self.warn(StackWarning::ReturnWithoutCall { line });
+                    continue;
+                }
+
+                for actual in [after.min, after.max] {
+                    match returned {
+                        Some(expected) if expected != actual => {
+                            self.warn(StackWarning::MismatchedReturnDepth { line, expected, actual });
+                        }
+                        Some(_) => {}
+                        None => returned = Some(actual),
+                    }
+                }
+            }
+
+            for target in successors {
+                if !back_edges.contains(&(index, target)) {
+                    depths.entry(target).and_modify(|d| *d = d.merge(after)).or_insert(after);
+                    continue;
+                }
+
+                // A loop must reach its start with the depths it started with.
+                if let Some(&seen) = depths.get(&target).filter(|&&seen| seen != after) {
+                    let delta = if after.max != seen.max { after.max - seen.max } else { after.min - seen.min };
+                    self.warn(StackWarning::UnbalancedLoop { line, delta });
+                }
+            }
+        }
+
+        let Entry::Subroutine = entry else { return Summary::NoReturn; };
+
+        match returned {
+            _ if !known => Summary::Unknown,
+            Some(delta) => Summary::Returns { required: -lowest, delta },
+            None => Summary::NoReturn,
+        }
+    }
+
+    /// Order the operations reachable from the start, with their successors, so that each operation comes after its predecessors.
+    /// The edges that jump back to an operation on the current path close a loop, and are returned separately.
+    fn order(&mut self, start: usize, known: &mut bool) -> (Vec<(usize, Vec<usize>)>, HashSet<Edge>) {
+        let mut visited = HashSet::from([start]);
+        let mut path = HashSet::from([start]);
+        let mut back_edges = HashSet::new();
+        let mut finished = vec![];
+
+        let successors = self.successors(start, known);
+        let mut stack = vec![(start, successors, 0)];
+
+        while let Some((index, successors, visits)) = stack.last_mut() {
+            let index = *index;
+
+            let Some(&target) = successors.get(*visits) else {
+                let (index, successors, _) = stack.pop().expect("the stack is not empty");
+                path.remove(&index);
+                finished.push((index, successors));
+                continue;
+            };
+
+            *visits += 1;
+
+            if path.contains(&target) {
+                back_edges.insert((index, target));
+            } else if visited.insert(target) {
+                path.insert(target);
+                let successors = self.successors(target, known);
+                stack.push((target, successors, 0));
+            }
+        }
+
+        finished.reverse();
+        (finished, back_edges)
+    }
+
+    /// Indices of the operations that can run after the operation.
+    /// Paths through calls to unknown subroutines and through dynamic stack effects are not followed.
+    fn successors(&mut self, index: usize, known: &mut bool) -> Vec<usize> {
+        let Some(op) = self.ops.get(index).copied() else { return vec![]; };
+        let next = self.next[index];
+
+        let addresses = match op {
+            Op::Call(address) => {
+                let Some(&target) = self.indices.get(&address) else { return vec![]; };
+
+                match self.summary(target, 0) {
+                    Summary::Returns { .. } => vec![next],
+                    Summary::NoReturn => vec![],
+                    Summary::InProgress | Summary::Unknown => {
+                        *known = false;
+                        vec![]
+                    }
+                }
+            }
+
+            _ if op.stack_effect().is_none() => {
+                *known = false;
+                vec![]
+            }
+
+            Op::Halt | Op::Eof | Op::Return => vec![],
+            Op::Jump(address) => vec![address],
+            Op::JumpZero(address) | Op::JumpNotZero(address) => vec![address, next],
+            _ => vec![next],
+        };
+
+        addresses.iter().filter_map(|address| self.indices.get(address).copied()).collect()
+    }
+}
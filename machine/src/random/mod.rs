@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+/// Seed used when no seed is given, so programs are deterministic by default.
+pub const DEFAULT_SEED: u32 = 0x5EED;
+
+/// Seedable pseudo-random number generator (mulberry32).
+/// The whole state is two numbers, so it is cheap to clone and serialize with the canvas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Random {
+    /// Seed the generator started from.
+    pub seed: u32,
+
+    /// Current state of the generator.
+    pub state: u32,
+}
+
+impl Random {
+    pub fn new(seed: u32) -> Random {
+        Random { seed, state: seed }
+    }
+
+    /// Rewind the generator back to its seed.
+    pub fn reset(&mut self) {
+        self.state = self.seed;
+    }
+
+    /// Derive an independent generator for the given stream, e.g. a machine id.
+    pub fn fork(&self, stream: u16) -> Random {
+        Random::new(self.seed ^ (stream as u32 + 1).wrapping_mul(0x9E3779B9))
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state = self.state.wrapping_add(0x6D2B79F5);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 15)).wrapping_mul(z | 1);
+        z ^= z.wrapping_add((z ^ (z >> 7)).wrapping_mul(z | 61));
+        z ^ (z >> 14)
+    }
+
+    pub fn next_u16(&mut self) -> u16 {
+        (self.next_u32() >> 16) as u16
+    }
+
+    /// Returns a value between min (inclusive) and max (exclusive).
+    /// Returns min if the range is empty.
+    pub fn range(&mut self, min: u16, max: u16) -> u16 {
+        if max <= min { return min; }
+
+        let span = (max - min) as u32;
+        min + ((self.next_u16() as u32 * span) >> 16) as u16
+    }
+}
+
+impl Default for Random {
+    fn default() -> Self {
+        Random::new(DEFAULT_SEED)
+    }
+}
+
+#[cfg(test)]
+mod random_tests {
+    use super::Random;
+
+    #[test]
+    fn test_deterministic() {
+        let mut a = Random::new(42);
+        let mut b = Random::new(42);
+        let values: Vec<u16> = (0..10).map(|_| a.next_u16()).collect();
+
+        for v in &values {
+            assert_eq!(b.next_u16(), *v);
+        }
+
+        // The generator replays the same sequence after a reset.
+        a.reset();
+        assert_eq!(a.next_u16(), values[0]);
+
+        assert_ne!(Random::new(43).next_u16(), values[0]);
+        assert_ne!(a.fork(1).next_u16(), a.fork(2).next_u16());
+    }
+
+    #[test]
+    fn test_range() {
+        let mut r = Random::new(1);
+
+        for _ in 0..1000 {
+            let v = r.range(10, 20);
+            assert!((10..20).contains(&v));
+        }
+
+        assert_eq!(r.range(5, 5), 5);
+        assert_eq!(r.range(9, 2), 9);
+    }
+}
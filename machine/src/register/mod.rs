@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::register::Register::{PC, SP};
 use crate::Register::FP;
-use crate::{CALL_STACK_START, STACK_START};
+use crate::mem::MemoryLayout;
 
 pub const REG_COUNT: usize = 0xF;
 
@@ -40,9 +40,14 @@ impl Registers {
     }
 
     pub fn reset(&mut self) {
+        self.reset_with(&MemoryLayout::default());
+    }
+
+    /// Reset the stack pointers to the bottom of the stacks in the memory layout.
+    pub fn reset_with(&mut self, layout: &MemoryLayout) {
         self.set(PC, 0);
-        self.set(SP, STACK_START - 1);
-        self.set(FP, CALL_STACK_START - 1);
+        self.set(SP, layout.stack_start() - 1);
+        self.set(FP, layout.call_stack_start() - 1);
     }
 
     pub fn get(&self, r: R) -> u16 {
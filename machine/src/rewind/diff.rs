@@ -1,3 +1,5 @@
+use crate::mem::{PagedBuffer, PAGE_SIZE};
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Patch<T> {
     pub index: usize,
@@ -5,6 +7,22 @@ pub struct Patch<T> {
     pub to: Option<T>,
 }
 
+/// Diffs two paged buffers, skipping the pages they share.
+pub fn diff_pages(a: &PagedBuffer, b: &PagedBuffer) -> Vec<Patch<u16>> {
+    let mut patches: Vec<Patch<u16>> = vec![];
+
+    for page in 0..a.page_count().max(b.page_count()) {
+        if a.shares_page(b, page) { continue; }
+
+        for mut patch in diff_slice(a.page(page), b.page(page)) {
+            patch.index += page * PAGE_SIZE;
+            patches.push(patch);
+        }
+    }
+
+    patches
+}
+
 pub fn diff_slice<T: PartialEq + Clone>(a: &[T], b: &[T]) -> Vec<Patch<T>> {
     let mut patches: Vec<Patch<T>> = vec![];
 
@@ -24,7 +42,8 @@ pub fn diff_slice<T: PartialEq + Clone>(a: &[T], b: &[T]) -> Vec<Patch<T>> {
 
 #[cfg(test)]
 mod diff_tests {
-    use crate::rewind::diff::{diff_slice, Patch};
+    use crate::mem::PagedBuffer;
+    use crate::rewind::diff::{diff_pages, diff_slice, Patch};
 
     #[test]
     fn diff_test() {
@@ -37,4 +56,19 @@ mod diff_tests {
         let patches = diff_slice(&[1, 2], &[1]);
         assert_eq!(patches[0], Patch { index: 1, from: Some(2), to: None });
     }
+
+    #[test]
+    fn diff_pages_test() {
+        let mut a = PagedBuffer::new(0x300);
+        a.set(0x10, 1);
+
+        let mut b = a.clone();
+        b.set(0x210, 2);
+        b.set(0x10, 3);
+
+        assert_eq!(diff_pages(&a, &b), [
+            Patch { index: 0x10, from: Some(1), to: Some(3) },
+            Patch { index: 0x210, from: Some(0), to: Some(2) },
+        ]);
+    }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 pub mod diff;
 
-use diff::{Patch, diff_slice};
+use diff::{Patch, diff_pages, diff_slice};
 use crate::blocks::Block;
 use crate::canvas::Canvas;
 use crate::canvas::wire::Wire;
@@ -51,7 +51,7 @@ impl Rewind {
                 if let Some(prev) = previous.seq.machines.iter().find(|m| m.id == curr.id) {
                     let id = curr.id.unwrap_or(0);
 
-                    let memory = diff_slice(&prev.mem.buffer, &curr.mem.buffer);
+                    let memory = diff_pages(&prev.mem.buffer, &curr.mem.buffer);
                     let register = diff_slice(&prev.reg.buffer, &curr.reg.buffer);
 
                     // let inbox = diff_slice(prev.inbox.make_contiguous(), &curr.inbox);
@@ -100,13 +100,13 @@ impl Rewind {
 
                 for patch in &mem.memory {
                     if let Some(from) = patch.from {
-                        m.mem.buffer[patch.index] = from;
+                        m.mem.set(patch.index as u16, from);
                     }
                 }
 
                 for patch in &mem.register {
                     if let Some(from) = patch.from {
-                        m.mem.buffer[patch.index] = from;
+                        m.reg.buffer[patch.index] = from;
                     }
                 }
             }
@@ -123,13 +123,13 @@ impl Rewind {
 
                 for patch in &mem.memory {
                     if let Some(to) = patch.to {
-                        m.mem.buffer[patch.index] = to;
+                        m.mem.set(patch.index as u16, to);
                     }
                 }
 
                 for patch in &mem.register {
                     if let Some(to) = patch.to {
-                        m.mem.buffer[patch.index] = to;
+                        m.reg.buffer[patch.index] = to;
                     }
                 }
             }
@@ -1,16 +1,27 @@
 pub mod status;
 pub mod seq_error;
+pub mod schedule;
+pub mod wait_graph;
+
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+mod parallel;
 
 use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
-use crate::{Actor, Event, Execute, Machine, Message, Parser};
+use crate::{Actor, Event, Execute, Host, HostFunctions, Machine, Message, Parser};
+use crate::random::{DEFAULT_SEED, Random};
+use crate::mem::MemoryLayout;
+use snafu::ensure;
+use seq_error::{InvalidMemoryLayoutSnafu, MachineDoesNotExistSnafu};
 
 use status::MachineStatus;
 use status::MachineStatus::{Awaiting, Halted, Running};
 
 pub use seq_error::SequencerError::*;
 pub use seq_error::SequencerError;
-use crate::status::MachineStatus::{Errored, Invalid, Loaded, Ready, Sleeping};
+pub use schedule::Schedule;
+pub use wait_graph::WaitForGraph;
+use crate::status::MachineStatus::{AwaitingInput, Blocked, Errored, Invalid, Loaded, Ready, Sleeping};
 
 type Errorable = Result<(), SequencerError>;
 type Statuses = HashMap<u16, MachineStatus>;
@@ -23,15 +34,27 @@ pub struct Sequencer {
     pub statuses: Statuses,
 
     /// We should disable the message watchdog if we know the message will eventually arrive.
+    /// The watchdog prevents the `receive` instruction from blocking forever.
     pub await_watchdog: bool,
 
-    /// Are all machines incapable of sending messages?
-    /// Use this to prevent the `receive` instruction from blocking forever.
-    await_watchdog_counter: u16,
-}
+    /// Current tick of the canvas. Incremented by `Canvas::tick`.
+    pub ticks: u16,
+
+    /// Seed for the random number generators of the machines.
+    pub seed: u32,
+
+    /// Clock speed and priority of the machines. Machines without a schedule use the default.
+    pub schedules: HashMap<u16, Schedule>,
 
-/// How many cycles should we wait for the message to be received?
-const MAX_WAIT_CYCLES: u16 = 3;
+    /// Host functions shared by every machine.
+    #[serde(skip)]
+    pub host: Host,
+
+    /// How many threads are used to step the machines?
+    /// Machines are stepped one after another unless built with the `parallel` feature.
+    #[serde(skip)]
+    pub threads: usize,
+}
 
 impl Sequencer {
     pub fn new() -> Sequencer {
@@ -39,22 +62,61 @@ impl Sequencer {
             machines: vec![],
             statuses: HashMap::new(),
             await_watchdog: true,
-            await_watchdog_counter: MAX_WAIT_CYCLES,
+            ticks: 0,
+            seed: DEFAULT_SEED,
+            schedules: HashMap::new(),
+            host: Host::default(),
+            threads: 1,
         }
     }
 
-    /// Add a machine.
+    /// Add a machine with the default memory layout.
     pub fn add(&mut self, id: u16) {
-        let mut machine = Machine::new();
+        self.push(id, MemoryLayout::default());
+    }
+
+    /// Add a machine with the given memory layout.
+    pub fn add_with_layout(&mut self, id: u16, layout: MemoryLayout) -> Errorable {
+        ensure!(layout.is_valid(), InvalidMemoryLayoutSnafu { id });
+
+        self.push(id, layout);
+        Ok(())
+    }
+
+    fn push(&mut self, id: u16, layout: MemoryLayout) {
+        let mut machine = Machine::with_layout(layout);
         machine.id = Some(id);
+        machine.host = self.host.clone();
+        machine.rng = Random::new(self.seed).fork(id);
 
         self.machines.push(machine);
     }
 
+    /// Re-seed the random number generators of every machine.
+    /// Each machine gets its own sequence, derived from the seed and the machine id.
+    pub fn set_seed(&mut self, seed: u32) {
+        self.seed = seed;
+
+        for machine in &mut self.machines {
+            let Some(id) = machine.id else { continue; };
+            machine.rng = Random::new(seed).fork(id);
+        }
+    }
+
+    /// Register the host functions for every machine.
+    pub fn set_host<H: HostFunctions + 'static>(&mut self, host: H) {
+        self.host = Host::new(host);
+
+        for machine in &mut self.machines {
+            machine.host = self.host.clone();
+        }
+    }
+
     /// Remove a machine.
     pub fn remove(&mut self, id: u16) {
         self.machines.retain(|m| m.id != Some(id));
         self.statuses.remove(&id);
+        self.schedules.remove(&id);
     }
 
     /// Load the code and symbols into memory.
@@ -62,18 +124,16 @@ impl Sequencer {
         let machine = self.get_mut(id).ok_or(MachineDoesNotExist { id })?;
         machine.full_reset();
 
-        let parser: Result<Parser, _> = (*source).try_into();
+        let mut parser = Parser::with_layout(source, machine.mem.layout);
 
-        let parser = match parser {
-            Ok(parser) => parser,
-            Err(error) => {
-                self.statuses.insert(id, Invalid);
-                return Err(CannotParse { id, error });
-            }
-        };
+        if let Err(error) = parser.parse() {
+            self.statuses.insert(id, Invalid);
+            return Err(CannotParse { id, error });
+        }
 
         machine.mem.load_code(parser.ops);
         machine.mem.load_symbols(parser.symbols);
+        machine.error_handler = parser.error_handler;
 
         self.statuses.insert(id, Loaded);
 
@@ -87,113 +147,139 @@ impl Sequencer {
 
             // Do not reset the machine if it is invalid.
             if self.statuses.get(&id) == Some(&Invalid) { continue; }
+            machine.ticks = self.ticks;
             machine.partial_reset();
 
-            self.await_watchdog_counter = MAX_WAIT_CYCLES;
+            if let Some(schedule) = self.schedules.get_mut(&id) {
+                schedule.reset();
+            }
+
             self.statuses.insert(id, Ready);
         }
     }
 
     /// Step a number of times for all machines.
     /// Messages must be routed before this method is called.
+    ///
+    /// A machine that fails is stopped, while the other machines keep running.
+    /// The first failure is returned after every machine has been stepped.
+    ///
+    /// Machines run in order of priority, for as many cycles as their schedule allows.
+    /// The count is used for machines that do not set their own clock speed.
     pub fn step(&mut self, count: u16) -> Errorable {
-        for machine in &mut self.machines {
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        if self.threads > 1 { return self.step_parallel(count); }
+
+        let mut failure = None;
+
+        for index in self.execution_order() {
+            let machine = &mut self.machines[index];
             let Some(id) = machine.id else { continue; };
-            let statuses = self.statuses.clone();
+            let Some(&status) = self.statuses.get(&id) else { continue; };
 
-            let Some(status) = statuses.get(&id) else { continue; };
-            let status = status.clone();
+            // Let the machine know what time it is.
+            machine.ticks = self.ticks;
 
-            // Manage state transitions of the machine.
-            match status {
-                Halted | Invalid | Loaded | Errored => continue,
+            let outcome = step_machine(machine, status, self.schedules.get_mut(&id), count);
+            self.apply(id, outcome, &mut failure);
+        }
 
-                Sleeping => {
-                    if machine.remaining_sleep_ticks > 0 {
-                        machine.remaining_sleep_ticks -= 1;
+        failure.map_or(Ok(()), Err)
+    }
 
-                        if machine.remaining_sleep_ticks == 0 {
-                            self.statuses.insert(id, Running);
-                            machine.sleeping = false;
-                        }
-                    }
+    /// Record the status and the failure of a stepped machine.
+    /// Only the first failure is kept.
+    fn apply(&mut self, id: u16, outcome: StepOutcome, failure: &mut Option<SequencerError>) {
+        if let Some(status) = outcome.status {
+            self.statuses.insert(id, status);
+        }
 
-                    continue
-                }
+        if let Some(error) = outcome.failure {
+            failure.get_or_insert(error);
+        }
+    }
 
-                Ready => {
-                    self.statuses.insert(id, Running);
-                }
+    /// Raise an error if an awaiting machine can never receive a message.
+    /// `links` lists the blocks connected to each machine by a wire, in either direction.
+    ///
+    /// Machines waiting on each other are reported as a deadlock.
+    /// Otherwise, the machine is waiting on peers that have stopped.
+    pub fn check_awaiting(&mut self, links: &HashMap<u16, Vec<u16>>) -> Errorable {
+        if !self.await_watchdog { return Ok(()); }
+
+        let graph = WaitForGraph::build(&self.machines, &self.statuses, links);
 
-                _ => {}
+        if let Some(ids) = graph.find_deadlock() {
+            for id in &ids {
+                self.statuses.insert(*id, Errored);
             }
 
-            // Before each instruction cycle, we collect and process the messages sequentially.
-            machine.receive_messages().map_err(|error| ReceiveFailed { error: error.into() })?;
-
-            // If a message is received, we resume the machine's execution.
-            // Otherwise, we suspend the machine's execution until subsequent cycles.
-            if status == Awaiting {
-                // Do not tick the machine if the still did not receive the message.
-                if machine.expected_receives > 0 {
-                    // Watchdog prevents the `receive` instruction from blocking forever.
-                    if self.await_watchdog {
-                        // Raise an error if all peers are halted.
-                        if self.await_watchdog_counter == 0 {
-                            self.statuses.insert(id, Errored);
-                            return Err(MessageNeverReceived { id });
-                        }
-
-                        // Decrement the watchdog counter
-                        if peers_halted(statuses.clone(), id) {
-                            self.await_watchdog_counter -= 1;
-                        }
-                    }
-
-                    continue;
-                }
+            return Err(Deadlock { ids });
+        }
 
-                // If it's the last instruction, we halt the machine as the message is received.
-                if machine.should_halt() {
-                    self.statuses.insert(id, Halted);
-                    continue;
-                }
+        if let Some(&id) = graph.blocked().first() {
+            self.statuses.insert(id, Errored);
+            return Err(MessageNeverReceived { id });
+        }
 
-                self.statuses.insert(id, Running);
-            }
+        Ok(())
+    }
 
-            for _ in 0..count {
-                // Execute the instruction.
-                machine.tick().map_err(|error| {
-                    self.statuses.insert(id, Errored);
-                    ExecutionFailed { id, error: error.into() }
-                })?;
-
-                // If the last instruction is a `receive`,
-                // we suspend the machine's execution until subsequent cycles,
-                // until the machine receives a message.
-                if machine.expected_receives > 0 {
-                    self.statuses.insert(id, Awaiting);
-                    break;
-                }
+    /// Indices of the machines in the order they run, from the highest priority.
+    /// Machines with the same priority run in the order they were added.
+    fn execution_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.machines.len()).collect();
 
-                // Sleep the machine.
-                if machine.sleeping {
-                    self.statuses.insert(id, Sleeping);
-                    break;
-                }
+        order.sort_by_key(|&index| {
+            let id = self.machines[index].id.unwrap_or_default();
+            std::cmp::Reverse(self.schedules.get(&id).map_or(0, |s| s.priority))
+        });
 
-                // Halt the machine if we reached the end of the program.
-                if machine.should_halt() {
-                    self.statuses.insert(id, Halted);
-                    break;
-                }
-            }
-        }
+        order
+    }
+
+    /// Set the clock speed and priority of the machine.
+    pub fn set_schedule(&mut self, id: u16, schedule: Schedule) -> Errorable {
+        ensure!(self.get(id).is_some(), MachineDoesNotExistSnafu { id });
+
+        self.schedules.insert(id, schedule);
+        Ok(())
+    }
+
+    /// Supply text to the machine, to be read by `read_char` and `read_line`.
+    pub fn provide_input(&mut self, id: u16, text: &str) -> Errorable {
+        let machine = self.get_mut(id).ok_or(MachineDoesNotExist { id })?;
+        machine.provide_input(text);
 
         Ok(())
     }
 
+    pub fn get_schedule(&self, id: u16) -> Schedule {
+        self.schedules.get(&id).copied().unwrap_or_default()
+    }
+
+    /// Return the undelivered messages to the outbox of their senders, to be sent again next tick.
+    /// Machines with undelivered messages are blocked until their messages are delivered.
+    pub fn hold_messages(&mut self, messages: Vec<Message>) {
+        for message in messages {
+            let Some(machine) = self.get_mut(message.sender.block) else { continue; };
+            machine.outbox.push(message);
+        }
+
+        for machine in &self.machines {
+            let Some(id) = machine.id else { continue; };
+            let Some(&status) = self.statuses.get(&id) else { continue; };
+
+            if matches!(status, Invalid | Loaded | Errored) { continue; }
+
+            if !machine.outbox.is_empty() {
+                self.statuses.insert(id, Blocked);
+            } else if status == Blocked {
+                self.statuses.insert(id, resume_status(machine));
+            }
+        }
+    }
+
     /// Wake the machine up from sleep.
     pub fn wake(&mut self, machine_id: u16) {
         // Resume the machine's execution state.
@@ -235,11 +321,113 @@ impl Sequencer {
     }
 }
 
-/// Are there no more active peers?
-pub fn peers_halted(statuses: Statuses, id: u16) -> bool {
-    let active = statuses.iter()
-        .filter(|(m_id, status)| id != **m_id && (status == &&Running || status == &&Ready))
-        .count();
+/// Result of stepping a single machine.
+#[derive(Debug, Default)]
+struct StepOutcome {
+    /// New status of the machine, if it changed.
+    status: Option<MachineStatus>,
+
+    failure: Option<SequencerError>,
+}
+
+impl StepOutcome {
+    fn status(status: MachineStatus) -> StepOutcome {
+        StepOutcome { status: Some(status), failure: None }
+    }
+
+    fn failed(failure: SequencerError) -> StepOutcome {
+        StepOutcome { status: Some(Errored), failure: Some(failure) }
+    }
+}
+
+/// Run a single machine for the tick, given its status at the start of the tick.
+/// Only touches the machine and its schedule, so machines can be stepped independently.
+fn step_machine(machine: &mut Machine, status: MachineStatus, schedule: Option<&mut Schedule>, count: u16) -> StepOutcome {
+    let Some(id) = machine.id else { return StepOutcome::default(); };
+    let mut outcome = StepOutcome::default();
+
+    // Manage state transitions of the machine.
+    match status {
+        Halted | Invalid | Loaded | Errored | Blocked => return outcome,
+
+        Sleeping => {
+            if machine.remaining_sleep_ticks > 0 {
+                machine.remaining_sleep_ticks -= 1;
+
+                if machine.remaining_sleep_ticks == 0 {
+                    machine.sleeping = false;
+                    return StepOutcome::status(Running);
+                }
+            }
+
+            return outcome;
+        }
+
+        // Keep waiting until the host supplies the input.
+        AwaitingInput if machine.awaiting_input => return outcome,
+
+        Ready | AwaitingInput => {
+            outcome.status = Some(Running);
+        }
+
+        _ => {}
+    }
+
+    let mut count = count;
+
+    if let Some(schedule) = schedule {
+        // Slow machines only run once every few ticks.
+        if !schedule.is_due() { return outcome; }
+
+        count = schedule.cycles_per_tick.unwrap_or(count);
+    }
+
+    // Before each instruction cycle, we collect and process the messages sequentially.
+    if let Err(error) = machine.receive_messages() {
+        return StepOutcome::failed(ReceiveFailed { error });
+    }
+
+    // If a message is received, we resume the machine's execution.
+    // Otherwise, we suspend the machine's execution until subsequent cycles.
+    if status == Awaiting {
+        // Do not tick the machine if the still did not receive the message.
+        if machine.expected_receives > 0 { return outcome; }
+
+        // If it's the last instruction, we halt the machine as the message is received.
+        if machine.should_halt() { return StepOutcome::status(Halted); }
+
+        outcome.status = Some(Running);
+    }
+
+    for _ in 0..count {
+        // Execute the instruction.
+        if let Err(error) = machine.tick() {
+            return StepOutcome::failed(ExecutionFailed { id, error });
+        }
+
+        // If the last instruction is a `receive`,
+        // we suspend the machine's execution until subsequent cycles,
+        // until the machine receives a message.
+        if machine.expected_receives > 0 { return StepOutcome::status(Awaiting); }
+
+        // Wait for the host to supply input.
+        if machine.awaiting_input { return StepOutcome::status(AwaitingInput); }
+
+        // Sleep the machine.
+        if machine.sleeping { return StepOutcome::status(Sleeping); }
+
+        // Halt the machine if we reached the end of the program.
+        if machine.should_halt() { return StepOutcome::status(Halted); }
+    }
+
+    outcome
+}
+
+fn resume_status(machine: &Machine) -> MachineStatus {
+    if machine.expected_receives > 0 { return Awaiting; }
+    if machine.awaiting_input { return AwaitingInput; }
+    if machine.sleeping { return Sleeping; }
+    if machine.should_halt() { return Halted; }
 
-    return active == 0;
+    Running
 }
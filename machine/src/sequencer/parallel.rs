@@ -0,0 +1,89 @@
+use std::panic::resume_unwind;
+use std::thread;
+use crate::Machine;
+use super::{step_machine, Errorable, Schedule, Sequencer, StepOutcome};
+
+impl Sequencer {
+    /// Step the machines on multiple threads.
+    ///
+    /// Machines share nothing during a step, as their messages are queued in their outboxes.
+    /// The outcomes are applied in the execution order, so the results are identical to `step`.
+    ///
+    /// Host functions may keep state, so the machines that can call them are stepped on this thread,
+    /// in the execution order. The host sees the same calls in the same order as with `step`.
+    pub(super) fn step_parallel(&mut self, count: u16) -> Errorable {
+        let ticks = self.ticks;
+        let order = self.execution_order();
+        let calls_host: Vec<bool> = self.machines.iter_mut().map(|machine| machine.may_call_host()).collect();
+
+        let statuses = &self.statuses;
+        let schedules = &mut self.schedules;
+
+        // Pair each machine with its own schedule, so the threads never share them.
+        let mut jobs: Vec<(&mut Machine, Option<Schedule>)> = self.machines.iter_mut()
+            .map(|machine| {
+                let schedule = machine.id.and_then(|id| schedules.get(&id).copied());
+                (machine, schedule)
+            })
+            .collect();
+
+        let step = |(machine, schedule): &mut (&mut Machine, Option<Schedule>)| {
+            let id = machine.id?;
+            let &status = statuses.get(&id)?;
+
+            // Let the machine know what time it is.
+            machine.ticks = ticks;
+
+            Some(step_machine(machine, status, schedule.as_mut(), count))
+        };
+
+        let mut outcomes: Vec<Option<StepOutcome>> = (0..jobs.len()).map(|_| None).collect();
+
+        // Split the jobs of the machines that call the host from the rest.
+        let mut host_jobs: Vec<(usize, &mut (&mut Machine, Option<Schedule>))> = vec![];
+        let mut thread_jobs = vec![];
+
+        for (index, job) in jobs.iter_mut().enumerate() {
+            if calls_host[index] { host_jobs.push((index, job)); } else { thread_jobs.push((index, job)); }
+        }
+
+        host_jobs.sort_by_key(|(index, _)| order.iter().position(|i| i == index));
+
+        let chunk_size = (thread_jobs.len() + self.threads - 1) / self.threads;
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = thread_jobs.chunks_mut(chunk_size.max(1))
+                .map(|chunk| scope.spawn(|| {
+                    chunk.iter_mut().map(|(index, job)| (*index, step(job))).collect::<Vec<_>>()
+                }))
+                .collect();
+
+            for (index, job) in host_jobs {
+                outcomes[index] = step(job);
+            }
+
+            for handle in handles {
+                for (index, outcome) in handle.join().unwrap_or_else(|panic| resume_unwind(panic)) {
+                    outcomes[index] = outcome;
+                }
+            }
+        });
+
+        // Write back the schedules advanced by the threads.
+        for (machine, schedule) in jobs {
+            let (Some(id), Some(schedule)) = (machine.id, schedule) else { continue; };
+            schedules.insert(id, schedule);
+        }
+
+        let mut failure = None;
+
+        for index in order {
+            let Some(id) = self.machines[index].id else { continue; };
+            let Some(outcome) = outcomes[index].take() else { continue; };
+
+            self.apply(id, outcome, &mut failure);
+        }
+
+        failure.map_or(Ok(()), Err)
+    }
+}
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use tsify::Tsify;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Scheduling options of a machine, i.e. its clock speed and priority.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Copy, Clone, Tsify)]
+#[tsify(into_wasm_abi, from_wasm_abi)]
+pub struct Schedule {
+    /// How many instructions should the machine run per tick?
+    /// Uses the clock speed of the canvas if not set.
+    pub cycles_per_tick: Option<u16>,
+
+    /// Run the machine once every N ticks, for machines slower than one instruction per tick.
+    pub tick_interval: u16,
+
+    /// Machines with a higher priority run first in each tick.
+    pub priority: i16,
+
+    /// How many ticks have passed since the machine last ran?
+    #[serde(skip)]
+    elapsed: u16,
+}
+
+impl Schedule {
+    pub fn new(cycles_per_tick: Option<u16>, tick_interval: u16, priority: i16) -> Schedule {
+        Schedule { cycles_per_tick, tick_interval, priority, elapsed: 0 }
+    }
+
+    /// Should the machine run in the current tick? Advances the schedule by one tick.
+    pub fn is_due(&mut self) -> bool {
+        let due = self.elapsed == 0;
+        self.elapsed = (self.elapsed + 1) % self.tick_interval.max(1);
+
+        due
+    }
+
+    /// Start counting the ticks from the beginning.
+    pub fn reset(&mut self) {
+        self.elapsed = 0;
+    }
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Schedule::new(None, 1, 0)
+    }
+}
+
+#[cfg(test)]
+mod schedule_tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_interval() {
+        let mut s = Schedule::new(None, 3, 0);
+        let runs: Vec<bool> = (0..7).map(|_| s.is_due()).collect();
+        assert_eq!(runs, [true, false, false, true, false, false, true]);
+
+        s.reset();
+        assert!(s.is_due());
+
+        // Zero is treated the same as running every tick.
+        let mut s = Schedule::new(None, 0, 0);
+        assert!(s.is_due() && s.is_due());
+    }
+}
@@ -23,6 +23,12 @@ pub enum SequencerError {
     #[snafu(display("program expects a message but they are never received"))]
     MessageNeverReceived { id: u16 },
 
+    #[snafu(display("machines {ids:?} are waiting on each other's messages"))]
+    Deadlock { ids: Vec<u16> },
+
     ExecutionCycleExceeded { id: u16 },
+
+    #[snafu(display("the memory layout of machine {id} does not fit in memory"))]
+    InvalidMemoryLayout { id: u16 },
 }
 
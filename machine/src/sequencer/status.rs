@@ -22,6 +22,9 @@ pub enum MachineStatus {
     /// Machine is awaiting a message.
     Awaiting,
 
+    /// Machine is waiting for the host to supply input.
+    AwaitingInput,
+
     /// Machine is sleeping for a pre-determined duration. zzzzzz!
     Sleeping,
 
@@ -30,4 +33,7 @@ pub enum MachineStatus {
 
     /// Machine has produced a runtime error.
     Errored,
+
+    /// Machine is waiting for room in the recipient's inbox to send its messages.
+    Blocked,
 }
\ No newline at end of file
@@ -0,0 +1,162 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use crate::Machine;
+use crate::status::MachineStatus;
+use crate::status::MachineStatus::{Awaiting, AwaitingInput, Ready, Running, Sleeping};
+
+/// Wait-for graph of the machines that are blocked on a `receive`.
+/// An edge from A to B means that A can only be woken up by a message from B.
+#[derive(Debug, Default, PartialEq)]
+pub struct WaitForGraph {
+    pub edges: BTreeMap<u16, BTreeSet<u16>>,
+}
+
+impl WaitForGraph {
+    /// Builds the graph of the awaiting machines that can never be woken up.
+    ///
+    /// `links` lists the blocks connected to each machine by a wire, in either direction,
+    /// as those are the only blocks that can send a message to the machine.
+    pub fn build(machines: &[Machine], statuses: &HashMap<u16, MachineStatus>, links: &HashMap<u16, Vec<u16>>) -> WaitForGraph {
+        let machine = |id: u16| machines.iter().find(|m| m.id == Some(id));
+        let status = |id: u16| statuses.get(&id).copied();
+
+        // Can the machine send a message at some point, without waiting on anyone?
+        let is_active = |id: u16| {
+            let Some(m) = machine(id) else { return false; };
+            !m.outbox.is_empty() || matches!(status(id), Some(Ready | Running | Sleeping | AwaitingInput))
+        };
+
+        let mut edges: BTreeMap<u16, BTreeSet<u16>> = BTreeMap::new();
+
+        for m in machines {
+            let Some(id) = m.id else { continue; };
+            if status(id) != Some(Awaiting) || m.expected_receives == 0 || !m.inbox.is_empty() { continue; }
+
+            let peers = links.get(&id).cloned().unwrap_or_default();
+
+            // Other blocks, such as clocks and memory, can send a message at any time.
+            if peers.iter().any(|&peer| machine(peer).is_none()) { continue; }
+            if peers.iter().any(|&peer| is_active(peer)) { continue; }
+
+            edges.insert(id, peers.into_iter().collect());
+        }
+
+        // Machines waiting on a peer that can still be woken up may be woken up as well.
+        loop {
+            let woken: Vec<u16> = edges.iter()
+                .filter(|(_, peers)| peers.iter().any(|p| status(*p) == Some(Awaiting) && !edges.contains_key(p)))
+                .map(|(id, _)| *id)
+                .collect();
+
+            if woken.is_empty() { break; }
+
+            for id in woken {
+                edges.remove(&id);
+            }
+        }
+
+        // Only keep the edges between blocked machines.
+        let blocked: BTreeSet<u16> = edges.keys().copied().collect();
+
+        for peers in edges.values_mut() {
+            peers.retain(|p| blocked.contains(p));
+        }
+
+        WaitForGraph { edges }
+    }
+
+    /// Machines that can never be woken up.
+    pub fn blocked(&self) -> Vec<u16> {
+        self.edges.keys().copied().collect()
+    }
+
+    /// Returns the machines that are waiting on each other, if any.
+    /// This is every machine that shares a cycle with the first cycle found.
+    pub fn find_deadlock(&self) -> Option<Vec<u16>> {
+        let cycle = self.find_cycle()?;
+        let from = self.reachable(cycle[0]);
+
+        let ids = from.into_iter()
+            .filter(|id| self.reachable(*id).contains(&cycle[0]))
+            .collect();
+
+        Some(ids)
+    }
+
+    /// Machines reachable from the given machine, including itself.
+    fn reachable(&self, id: u16) -> BTreeSet<u16> {
+        let mut visited = BTreeSet::from([id]);
+        let mut queue = vec![id];
+
+        while let Some(id) = queue.pop() {
+            for &peer in self.edges.get(&id).into_iter().flatten() {
+                if visited.insert(peer) { queue.push(peer); }
+            }
+        }
+
+        visited
+    }
+
+    /// Returns the first cycle of machines waiting on each other, if any.
+    pub fn find_cycle(&self) -> Option<Vec<u16>> {
+        let mut visited = BTreeSet::new();
+
+        for &start in self.edges.keys() {
+            let mut path = vec![];
+
+            if let Some(cycle) = self.visit(start, &mut path, &mut visited) {
+                return Some(cycle);
+            }
+        }
+
+        None
+    }
+
+    fn visit(&self, id: u16, path: &mut Vec<u16>, visited: &mut BTreeSet<u16>) -> Option<Vec<u16>> {
+        // We came back to a machine on the current path.
+        if let Some(index) = path.iter().position(|p| *p == id) {
+            return Some(path[index..].to_vec());
+        }
+
+        if !visited.insert(id) { return None; }
+
+        path.push(id);
+
+        for &peer in self.edges.get(&id).into_iter().flatten() {
+            if let Some(cycle) = self.visit(peer, path, visited) {
+                return Some(cycle);
+            }
+        }
+
+        path.pop();
+        None
+    }
+}
+
+#[cfg(test)]
+mod wait_graph_tests {
+    use super::*;
+
+    fn graph(edges: &[(u16, &[u16])]) -> WaitForGraph {
+        WaitForGraph {
+            edges: edges.iter().map(|(id, peers)| (*id, peers.iter().copied().collect())).collect(),
+        }
+    }
+
+    #[test]
+    fn test_find_cycle() {
+        assert_eq!(graph(&[(0, &[1]), (1, &[0])]).find_cycle(), Some(vec![0, 1]));
+        assert_eq!(graph(&[(0, &[1]), (1, &[2]), (2, &[1])]).find_cycle(), Some(vec![1, 2]));
+        assert_eq!(graph(&[(0, &[1]), (1, &[])]).find_cycle(), None);
+        assert_eq!(graph(&[(0, &[1, 2]), (1, &[2]), (2, &[])]).find_cycle(), None);
+    }
+
+    #[test]
+    fn test_find_deadlock() {
+        // Machine 3 waits on the deadlock, but is not part of it.
+        let g = graph(&[(0, &[1]), (1, &[2]), (2, &[0]), (3, &[1])]);
+        assert_eq!(g.find_cycle(), Some(vec![0, 1, 2]));
+        assert_eq!(g.find_deadlock(), Some(vec![0, 1, 2]));
+
+        assert_eq!(graph(&[(0, &[1]), (1, &[])]).find_deadlock(), None);
+    }
+}
@@ -0,0 +1,17 @@
+; Checks the arithmetic with assertions, as run by `machine_cli test`.
+push 6
+push 7
+mul
+dup
+push 42
+assert_eq
+
+push 40
+greater_than
+assert
+
+push 5
+print_int
+
+; expect-stack:
+; expect-output: 5
@@ -1,3 +1,4 @@
+; test-skip: reads the memory-mapped segment, which needs a canvas peripheral
 .value LEN 4
 .value PACKED_PTR 0x5000
 
@@ -9,3 +9,5 @@ add_pattern:
 start:
     call add_pattern
     call add_pattern
+
+; expect-stack: 0xAA 0b11001100 1024 0xAA 204 1024
@@ -9,3 +9,6 @@ print
 
 load_string sunshine
 print
+
+; expect-output: Hello, world!
+; expect-output: Sunshine!
@@ -0,0 +1,42 @@
+; Reads a line and checks whether it reads the same backwards.
+.string line "                                                                "
+.string yes "%s is a palindrome"
+.string no "%s is not a palindrome"
+
+read_line line
+dup
+jump_zero palindrome
+
+; Walk inwards from both ends: [end, start]
+dec
+push 0
+
+check:
+    ; Stop once the two ends meet.
+    dup
+    pick 2
+    greater_than_or_equal
+    jump_not_zero palindrome
+
+    ; Compare the characters at both ends.
+    dup
+    char_at line
+    pick 2
+    char_at line
+    not_equal
+    jump_not_zero not_palindrome
+
+    inc
+    swap
+    dec
+    swap
+    jump check
+
+palindrome:
+    push line
+    print_fmt yes
+    halt
+
+not_palindrome:
+    push line
+    print_fmt no
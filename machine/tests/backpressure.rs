@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod backpressure_tests {
+    use machine::canvas::{Canvas, CanvasError, OverflowPolicy};
+    use machine::canvas::wire::port;
+    use machine::status::MachineStatus::{Blocked, Halted};
+
+    type Errorable = Result<(), CanvasError>;
+
+    /// Machine 0 sends five values in a single tick, while machine 1 holds two messages at most.
+    fn flood(policy: OverflowPolicy, receives: usize) -> Result<Canvas, CanvasError> {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.add_machine()?;
+        c.connect(port(0, 0), port(1, 0))?;
+
+        c.inbox_limit = 2;
+        c.machine_cycle_per_tick = 10;
+        c.set_overflow_policy(1, policy)?;
+
+        c.load_program(0, "
+            push 1
+            send 0 1
+            push 2
+            send 0 1
+            push 3
+            send 0 1
+            push 4
+            send 0 1
+            push 5
+            send 0 1
+        ")?;
+
+        c.load_program(1, &format!("sleep_tick 3\n{}", "receive\n".repeat(receives)))?;
+        Ok(c)
+    }
+
+    #[test]
+    fn test_drop_oldest() -> Errorable {
+        let mut c = flood(OverflowPolicy::DropOldest, 2)?;
+        c.run()?;
+
+        assert_eq!(c.seq.get(1).unwrap().mem.read_stack(2), [4, 5]);
+        assert_eq!(c.mailbox(1)?.dropped, 3);
+        assert_eq!(c.wire_dropped.get(&0), Some(&3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_drop_newest() -> Errorable {
+        let mut c = flood(OverflowPolicy::DropNewest, 2)?;
+        c.run()?;
+
+        assert_eq!(c.seq.get(1).unwrap().mem.read_stack(2), [1, 2]);
+        assert_eq!(c.mailbox(1)?.dropped, 3);
+        assert_eq!(c.wire_dropped.get(&0), Some(&3));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_sender() -> Errorable {
+        let mut c = flood(OverflowPolicy::BlockSender, 5)?;
+        c.seq.ready();
+        c.tick(2)?;
+
+        // The sender holds on to the messages that do not fit.
+        assert_eq!(c.seq.statuses[&0], Blocked);
+        assert_eq!(c.seq.get(0).unwrap().outbox.len(), 3);
+
+        // The receiver makes room for one message per tick.
+        c.tick(10)?;
+
+        assert_eq!(c.seq.get(1).unwrap().mem.read_stack(5), [1, 2, 3, 4, 5]);
+        assert_eq!(c.seq.statuses[&0], Halted);
+        assert_eq!(c.seq.statuses[&1], Halted);
+        assert_eq!(c.mailbox(1)?.dropped, 0);
+        assert!(c.wire_dropped.is_empty());
+
+        Ok(())
+    }
+}
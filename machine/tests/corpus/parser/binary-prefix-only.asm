@@ -0,0 +1 @@
+push 0b
\ No newline at end of file
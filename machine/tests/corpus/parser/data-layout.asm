@@ -0,0 +1,6 @@
+.value a 1
+.value b 2
+.string c "xy"
+push a
+push b
+load_string c
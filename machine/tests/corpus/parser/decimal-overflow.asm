@@ -0,0 +1 @@
+push 99999
\ No newline at end of file
@@ -0,0 +1 @@
+.on_error
\ No newline at end of file
@@ -0,0 +1 @@
+push 0x
\ No newline at end of file
@@ -0,0 +1 @@
+push 0b102
\ No newline at end of file
@@ -0,0 +1 @@
+push 0xZZ
\ No newline at end of file
@@ -0,0 +1 @@
+push
\ No newline at end of file
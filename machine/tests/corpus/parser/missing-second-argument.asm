@@ -0,0 +1 @@
+send 0
\ No newline at end of file
@@ -0,0 +1,3 @@
+é0 push 0é
+0
+05
\ No newline at end of file
@@ -0,0 +1 @@
+.string
\ No newline at end of file
@@ -0,0 +1 @@
+.string s
\ No newline at end of file
@@ -0,0 +1 @@
+jump nowhere
\ No newline at end of file
@@ -0,0 +1 @@
+.string s "unterminated
\ No newline at end of file
@@ -0,0 +1,2 @@
+; ünïcödé ✨ comment
+push 1 ; 🎉
@@ -0,0 +1,2 @@
+.string s "héllo ✨"
+load_string s
@@ -0,0 +1 @@
+.value v
\ No newline at end of file
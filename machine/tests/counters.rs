@@ -0,0 +1,55 @@
+#[cfg(test)]
+mod counter_tests {
+    use machine::{Execute, Machine, Op, RuntimeError};
+    use machine::canvas::{Canvas, CanvasError};
+    use machine::canvas::wire::port;
+
+    #[test]
+    fn test_cycles() -> Result<(), RuntimeError> {
+        let mut m: Machine = vec![Op::Push(1), Op::Pop, Op::Cycles, Op::Cycles].into();
+        m.run()?;
+
+        assert_eq!(m.mem.read_stack(2), [2, 3]);
+        assert_eq!(m.cycles, 4);
+
+        m.partial_reset();
+        assert_eq!(m.cycles, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_canvas_ticks() -> Result<(), CanvasError> {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.load_program(0, "push 0\npop\nticks\nsleep_tick 2\nticks\ncycles")?;
+        c.seq.ready();
+        c.tick(10)?;
+
+        // The machine sleeps for two ticks before resuming.
+        assert_eq!(c.seq.get(0).unwrap().mem.read_stack(3), [3, 7, 5]);
+        assert_eq!(c.seq.ticks, 10);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ticks_since_receive() -> Result<(), CanvasError> {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.add_machine()?;
+        c.connect(port(1, 0), port(0, 0))?;
+
+        c.load_program(0, "receive\npop\npush 0\npop\nticks_since_receive")?;
+        c.load_program(1, "push 0\npop\npush 5\nsend 0 1")?;
+        c.seq.ready();
+        c.tick(10)?;
+
+        // The message is received on the 5th tick, and read 3 ticks later.
+        let m = c.seq.get(0).unwrap();
+        assert_eq!(m.last_receive_tick, 5);
+        assert_eq!(m.mem.read_stack(1), [3]);
+
+        Ok(())
+    }
+}
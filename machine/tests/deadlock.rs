@@ -0,0 +1,115 @@
+#[cfg(test)]
+mod deadlock_tests {
+    use machine::canvas::{Canvas, CanvasError, CanvasError::MachineError};
+    use machine::canvas::wire::port;
+    use machine::blocks::BlockData::Clock;
+    use machine::status::MachineStatus::{Errored, Halted};
+    use machine::{Deadlock, MessageNeverReceived};
+
+    type Errorable = Result<(), CanvasError>;
+
+    #[test]
+    fn test_machines_waiting_on_each_other() -> Errorable {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.add_machine()?;
+        c.connect(port(0, 0), port(1, 0))?;
+        c.connect(port(1, 0), port(0, 0))?;
+
+        c.load_program(0, "receive\nsend 0 1")?;
+        c.load_program(1, "receive\nsend 0 1")?;
+
+        assert_eq!(c.run(), Err(MachineError { cause: Deadlock { ids: vec![0, 1] } }));
+        assert_eq!(c.seq.statuses[&0], Errored);
+        assert_eq!(c.seq.statuses[&1], Errored);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deadlock_does_not_stop_other_machines() -> Errorable {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.add_machine()?;
+        c.add_machine()?;
+        c.connect(port(0, 0), port(1, 0))?;
+        c.connect(port(1, 0), port(0, 0))?;
+
+        c.load_program(0, "receive\nsend 0 1")?;
+        c.load_program(1, "receive\nsend 0 1")?;
+        c.load_program(2, "push 0\nloop:\ninc\ndup\npush 50\nless_than\njump_not_zero loop")?;
+
+        assert_eq!(c.run(), Err(MachineError { cause: Deadlock { ids: vec![0, 1] } }));
+        assert_eq!(c.seq.statuses[&2], Halted);
+        assert_eq!(c.seq.get_mut(2).unwrap().stack().peek(), 50);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_deadlock_ring() -> Errorable {
+        let mut c = Canvas::new();
+
+        for id in 0..4 {
+            c.add_machine()?;
+            c.load_program(id, "receive")?;
+        }
+
+        // Machines 0, 1 and 2 form a ring, and machine 3 hangs off machine 1.
+        c.connect(port(0, 0), port(1, 0))?;
+        c.connect(port(1, 0), port(2, 0))?;
+        c.connect(port(2, 0), port(0, 0))?;
+        c.connect(port(1, 1), port(3, 0))?;
+
+        // Wires can carry replies both ways, so machine 3 and machine 1 also wait on each other.
+        assert_eq!(c.run(), Err(MachineError { cause: Deadlock { ids: vec![0, 1, 2, 3] } }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_slow_sender() -> Errorable {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.add_machine()?;
+        c.connect(port(0, 0), port(1, 0))?;
+
+        c.load_program(0, "sleep_tick 10\npush 5\nsend 0 1")?;
+        c.load_program(1, "receive")?;
+
+        c.run()?;
+        assert_eq!(c.seq.statuses[&1], Halted);
+        assert_eq!(c.seq.get_mut(1).unwrap().stack().peek(), 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_waiting_on_halted_peer() -> Errorable {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.add_machine()?;
+        c.connect(port(0, 0), port(1, 0))?;
+
+        c.load_program(0, "push 5")?;
+        c.load_program(1, "receive")?;
+
+        assert_eq!(c.run(), Err(MachineError { cause: MessageNeverReceived { id: 1 } }));
+        Ok(())
+    }
+
+    #[test]
+    fn test_waiting_on_block() -> Errorable {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        let clock = c.add_block(Clock { time: 0, freq: 4, ping: false })?;
+        c.connect(port(clock, 0), port(0, 0))?;
+
+        c.load_program(0, "receive\nreceive")?;
+
+        c.run()?;
+        assert_eq!(c.seq.statuses[&0], Halted);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod decode_cache_tests {
+    use machine::{test_helper::load_test_program, Execute, Machine, Op};
+
+    /// Run the machine with and without the decode cache.
+    fn run_both(mut m: Machine) -> (Machine, Machine) {
+        let mut uncached = m.clone();
+        uncached.mem.decoded.enabled = false;
+
+        m.run().expect("cannot run with the decode cache");
+        uncached.run().expect("cannot run without the decode cache");
+
+        (m, uncached)
+    }
+
+    #[test]
+    fn test_same_results() {
+        for file in ["call-stack-1.asm", "hello-world.asm"] {
+            let (cached, uncached) = run_both(load_test_program(file));
+
+            assert!(cached.mem.decoded.is_built());
+            assert!(!uncached.mem.decoded.is_built());
+
+            assert_eq!(cached.reg, uncached.reg, "{file} must end in the same state");
+            assert_eq!(cached.mem.buffer, uncached.mem.buffer, "{file} must end in the same state");
+            assert_eq!(cached.cycles, uncached.cycles);
+        }
+    }
+
+    #[test]
+    fn test_self_modifying_loop() {
+        // Count down by rewriting the argument of `push` on every iteration.
+        let mut m: Machine = vec![
+            Op::Push(3),          // [0] counter
+            Op::Dec,              // [2]
+            Op::Dup,              // [3]
+            Op::Store(1),         // [4] rewrite the counter of `push`
+            Op::Dup,              // [6]
+            Op::JumpNotZero(0),   // [7]
+        ].into();
+
+        m.mem.self_modifying = true;
+
+        let (cached, uncached) = run_both(m);
+        assert_eq!(cached.mem.read_stack(4), uncached.mem.read_stack(4));
+        assert_eq!(cached.mem.get(1), 0);
+    }
+
+    #[test]
+    fn test_invalidate_on_write() {
+        let mut m: Machine = vec![Op::Push(1), Op::Push(2)].into();
+        m.run().expect("cannot run the program");
+        assert!(m.mem.decoded.is_built());
+
+        // Clones share the decoded instructions until the code changes.
+        let mut clone = m.clone();
+        assert!(clone.mem.decoded.is_built());
+
+        clone.mem.write(3, &[5]);
+        assert!(!clone.mem.decoded.is_built());
+        assert!(m.mem.decoded.is_built());
+
+        // Writing outside of the code segment keeps the cache.
+        m.mem.write(m.mem.layout.data_start(), &[5]);
+        assert!(m.mem.decoded.is_built());
+    }
+}
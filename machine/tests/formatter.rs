@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod formatter_tests {
+    use machine::{format_source, Parser};
+
+    fn assert_same_program(a: &str, b: &str) {
+        let (a, b): (Parser, Parser) = (a.try_into().unwrap(), b.try_into().unwrap());
+
+        assert_eq!(a.ops, b.ops);
+        assert_eq!(a.symbols.bytes(), b.symbols.bytes());
+        assert_eq!(a.error_handler, b.error_handler);
+    }
+
+    #[test]
+    fn test_format_source() {
+        let src = r#"
+.string   hello "Hello, world!"   ; greeting
+.value bar 0xDEAD
+.value  longer_name   5
+
+
+; entry
+JUMP start
+add_pattern:   ; pushes three values
+push 0xAA        ; 170
+  push 0b11001100  ; 204
+      Push   01024 ; 1024
+RETURN
+start: call add_pattern
+ ; then print
+  load_string   hello
+  print
+"#;
+
+        let expected = r#".string hello       "Hello, world!" ; greeting
+.value  bar         0xDEAD
+.value  longer_name 5
+
+; entry
+jump start
+add_pattern: ; pushes three values
+    push 0xAA       ; 170
+    push 0b11001100 ; 204
+    push 01024      ; 1024
+    return
+start:
+    call add_pattern
+    ; then print
+    load_string hello
+    print
+"#;
+
+        // Uppercase instructions do not parse until they are formatted.
+        assert_eq!(format_source(src), expected);
+        assert!(Parser::try_from(expected).is_ok());
+    }
+
+    #[test]
+    fn test_align_operands() {
+        let src = "loop:\npush 1\nload_string msg\nsend 2 3\nhalt\n.string msg \"hi\"";
+        let expected = "loop:\n    push        1\n    load_string msg\n    send        2 3\n    halt\n.string msg \"hi\"\n";
+
+        assert_eq!(format_source(src), expected);
+    }
+
+    #[test]
+    fn test_comment_follows_next_code() {
+        let src = "start:\n; before the loop\n\npush 1\n; next label\nend:\nhalt";
+        let expected = "start:\n    ; before the loop\n\n    push 1\n; next label\nend:\n    halt\n";
+
+        assert_eq!(format_source(src), expected);
+    }
+
+    #[test]
+    fn test_comment_after_multi_line_string() {
+        let src = ".string s \"a;\nb\"   ; two lines\npush 1";
+        let formatted = format_source(src);
+
+        assert_eq!(formatted, ".string s \"a;\nb\" ; two lines\npush 1\n");
+        assert_same_program(src, &formatted);
+    }
+
+    #[test]
+    fn test_unscannable_source_is_kept() {
+        let src = "push 0xZZ\n  pop";
+        assert_eq!(format_source(src), src);
+    }
+
+    #[test]
+    fn test_format_test_programs() {
+        for src in [
+            include_str!("asm/assertions.asm"),
+            include_str!("asm/bitpacking.asm"),
+            include_str!("asm/call-stack-1.asm"),
+            include_str!("asm/hello-world.asm"),
+            include_str!("asm/palindrome.asm"),
+        ] {
+            let formatted = format_source(src);
+
+            assert_eq!(format_source(&formatted), formatted, "formatting is not idempotent");
+            assert_same_program(src, &formatted);
+        }
+    }
+}
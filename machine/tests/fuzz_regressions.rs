@@ -0,0 +1,96 @@
+#[cfg(test)]
+mod fuzz_regressions_tests {
+    use std::fs;
+    use machine::{Machine, ParseError, Parser, Scanner, Symbols};
+    use machine::binary::bytes::u8_vec_to_u16;
+    use machine::random::Random;
+    use machine::run::load_from_binary;
+    use machine::ParseError::{InvalidArgument, InvalidBinaryDigit, UnexpectedEndOfProgram};
+
+    /// Inputs that used to panic or misbehave, found by the fuzz targets in `machine/fuzz`.
+    fn corpus(kind: &str) -> Vec<(String, Vec<u8>)> {
+        let dir = env!("CARGO_MANIFEST_DIR").to_owned() + "/tests/corpus/" + kind;
+
+        fs::read_dir(dir).expect("cannot read the corpus").map(|entry| {
+            let path = entry.expect("cannot read the corpus entry").path();
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+
+            (name, fs::read(path).expect("cannot read the corpus file"))
+        }).collect()
+    }
+
+    /// Assemble the source the same way as the `parse` fuzz target.
+    fn assemble(source: &str) -> Result<Machine, ParseError> {
+        let mut scanner = Scanner::new(source);
+        let _ = scanner.scan_tokens();
+
+        source.try_into()
+    }
+
+    #[test]
+    fn test_parser_corpus() {
+        let entries = corpus("parser");
+        assert!(!entries.is_empty());
+
+        for (_, bytes) in entries {
+            let _ = assemble(&String::from_utf8_lossy(&bytes));
+        }
+    }
+
+    #[test]
+    fn test_binary_corpus() {
+        for (name, bytes) in corpus("binary") {
+            let result = load_from_binary(&u8_vec_to_u16(bytes));
+            assert_eq!(result.is_ok(), name == "valid.bin", "{name} is not loaded as expected");
+        }
+    }
+
+    #[test]
+    fn test_incomplete_instructions() {
+        let mut p = Parser::new("push");
+        assert_eq!(p.parse(), Err(InvalidArgument { errors: vec![UnexpectedEndOfProgram] }));
+
+        let mut p = Parser::new("push 0b2");
+        assert_eq!(p.parse(), Err(InvalidBinaryDigit { text: "0b2".into() }));
+
+        let mut p = Parser::new(".on_error");
+        assert_eq!(p.parse(), Err(UnexpectedEndOfProgram));
+    }
+
+    #[test]
+    fn test_symbol_bytes() -> Result<(), ParseError> {
+        let p: Parser = ".value a 7\n.string s \"hi\"\n.value b 9\npush a".try_into()?;
+        let symbols: &Symbols = &p.symbols;
+
+        // Each symbol is placed at its own offset, without shifting the others.
+        assert_eq!(symbols.offsets["s"], 2);
+        assert_eq!(symbols.bytes(), [7, 0, 'h' as u16, 'i' as u16, 0, 9]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_oversized_program() {
+        let mut p = Parser::new(&"push 1\n".repeat(40000));
+        assert!(p.parse().is_err());
+    }
+
+    #[test]
+    fn test_random_sources() {
+        let fragments = [
+            "push", "send", "0", "0x", "0b", "1", "0xFFFF", "99999", ".string", ".value", ".on_error",
+            "\"", "\"é\"", "label:", "label", ";", "✨", "\n", " ", ":", ".", "jump", "load_string",
+        ];
+
+        let mut rng = Random::new(41);
+
+        for _ in 0..3000 {
+            let len = rng.range(1, 12);
+
+            let source: String = (0..len)
+                .map(|_| fragments[rng.range(0, fragments.len() as u16 - 1) as usize])
+                .collect();
+
+            let _ = assemble(&source);
+        }
+    }
+}
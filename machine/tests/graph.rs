@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod graph_tests {
+    use machine::{CallEdge, ControlFlowGraph, Edge, EdgeKind, Parser};
+
+    fn graph(src: &str) -> ControlFlowGraph {
+        let parser: Parser = src.try_into().expect("cannot parse the program");
+        ControlFlowGraph::new(&parser)
+    }
+
+    fn edge(from: usize, to: usize, kind: EdgeKind) -> Edge {
+        Edge { from, to, kind }
+    }
+
+    #[test]
+    fn test_basic_blocks() {
+        let g = graph(r"
+            push 10
+        loop:
+            dec
+            dup
+            jump_not_zero loop
+            halt
+        ");
+
+        let ops: Vec<_> = g.blocks.iter().map(|block| block.ops.clone()).collect();
+
+        assert_eq!(ops, [
+            vec!["push 10"],
+            vec!["dec", "dup", "jump_not_zero loop"],
+            vec!["halt"],
+        ]);
+
+        assert_eq!(g.blocks[1].labels, ["loop"]);
+        assert_eq!((g.blocks[1].start, g.blocks[1].end), (2, 6));
+        assert_eq!(g.blocks[1].lines, [3, 4, 5]);
+
+        assert_eq!(g.edges, [
+            edge(0, 1, EdgeKind::Next),
+            edge(1, 1, EdgeKind::Branch),
+            edge(1, 2, EdgeKind::Next),
+        ]);
+    }
+
+    #[test]
+    fn test_unreachable_code() {
+        let g = graph(r"
+            jump end
+            push 1
+            push 2
+        end:
+            halt
+        orphan:
+            push 3
+        ");
+
+        let reachable: Vec<_> = g.blocks.iter().map(|block| block.reachable).collect();
+        assert_eq!(reachable, [true, false, true, false]);
+    }
+
+    #[test]
+    fn test_error_handler_is_reachable() {
+        let g = graph(".on_error handler\nhalt\nhandler:\npop\nreturn");
+
+        assert!(g.blocks.iter().all(|block| block.reachable));
+        assert_eq!(g.functions.last().map(|f| f.name.as_str()), Some("handler"));
+    }
+
+    #[test]
+    fn test_call_graph() {
+        let g = graph(include_str!("asm/call-stack-1.asm"));
+
+        let names: Vec<_> = g.functions.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["main", "add_pattern"]);
+        assert_eq!(g.calls, [CallEdge { caller: 0, callee: 1 }]);
+
+        // The subroutine body is not part of the caller.
+        assert_eq!(g.functions[0].blocks, [0, 2, 3]);
+        assert_eq!(g.functions[1].blocks, [1]);
+    }
+
+    #[test]
+    fn test_nested_calls() {
+        let g = graph(r"
+            call outer
+            halt
+        outer:
+            call inner
+            return
+        inner:
+            return
+        ");
+
+        assert_eq!(g.calls, [CallEdge { caller: 0, callee: 1 }, CallEdge { caller: 1, callee: 2 }]);
+    }
+
+    #[test]
+    fn test_dot_export() {
+        let g = graph(include_str!("asm/call-stack-1.asm"));
+        let dot = g.to_dot();
+
+        assert!(dot.starts_with("digraph program {"));
+        assert!(dot.contains("b2 [label=\"start:\\l    call add_pattern\\l\"];"));
+        assert!(dot.contains("b0 -> b2 [label=\"jump\"];"));
+        assert!(dot.contains("b2 -> b1 [label=\"call\", style=dashed];"));
+
+        let calls = g.to_call_graph_dot();
+        assert!(calls.contains("f1 [label=\"add_pattern\"];"));
+        assert!(calls.contains("f0 -> f1;"));
+    }
+
+    #[test]
+    fn test_json_export() {
+        let g = graph(include_str!("asm/call-stack-1.asm"));
+
+        let json = serde_json::to_string(&g).expect("cannot serialize the graph");
+        let parsed: ControlFlowGraph = serde_json::from_str(&json).expect("cannot deserialize the graph");
+
+        assert_eq!(parsed, g);
+        assert!(json.contains("\"kind\":\"Call\""));
+    }
+}
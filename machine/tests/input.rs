@@ -0,0 +1,87 @@
+#[cfg(test)]
+mod input_tests {
+    use machine::canvas::{Canvas, CanvasError};
+    use machine::cli::run_with_io;
+    use machine::status::MachineStatus::{AwaitingInput, Halted};
+    use machine::mem::Access;
+    use machine::RuntimeError::SegmentationFault;
+    use machine::{test_helper::load_test_program, Execute, Machine};
+
+    type Errorable = Result<(), CanvasError>;
+
+    /// Run the program with the given input, returning the printed lines.
+    fn run_with_input(mut m: Machine, input: &str) -> String {
+        let mut output = vec![];
+        run_with_io(&mut m, input.as_bytes(), &mut output).expect("cannot run the program");
+
+        String::from_utf8(output).expect("output is not valid utf-8")
+    }
+
+    #[test]
+    fn test_palindrome() {
+        let check = |word: &str| run_with_input(load_test_program("palindrome.asm"), &format!("{word}\n"));
+
+        assert_eq!(check("racecar"), "racecar is a palindrome\n");
+        assert_eq!(check("kayak"), "kayak is a palindrome\n");
+        assert_eq!(check("canvas"), "canvas is not a palindrome\n");
+    }
+
+    #[test]
+    fn test_read_char() {
+        let mut m: Machine = "read_char\nread_char\nread_char\nread_char".try_into().expect("cannot parse");
+        m.provide_input("hé");
+        m.run().expect("cannot run the program");
+
+        // The input is closed once the machine runs out of it, so reads push zero.
+        assert_eq!(m.mem.read_stack(4), ['h' as u16, 'é' as u16, 0, 0]);
+    }
+
+    #[test]
+    fn test_read_line_without_host() {
+        let mut m: Machine = ".string s \"     \"\nread_line s\nread_line s".try_into().expect("cannot parse");
+        m.provide_input("ab\r\ncd");
+        m.run().expect("cannot run the program");
+
+        // The second line is read when the input is closed.
+        assert_eq!(m.mem.read_stack(2), [2, 2]);
+        assert!(m.input.is_empty());
+    }
+
+    #[test]
+    fn test_read_line_too_long() {
+        let mut m: Machine = ".string s \"     \"\nread_line s".try_into().expect("cannot parse");
+        m.provide_input(&"a".repeat(65535));
+        m.close_input();
+
+        let error = m.run().expect_err("the line cannot fit in memory");
+        assert_eq!(error, SegmentationFault { addr: u16::MAX, access: Access::Write });
+        assert_eq!(m.input.len(), 65535);
+    }
+
+    #[test]
+    fn test_awaiting_input() -> Errorable {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.load_program(0, ".string name \"        \"\nread_line name\nread_char")?;
+
+        c.seq.ready();
+        c.tick(3)?;
+        assert_eq!(c.seq.statuses[&0], AwaitingInput);
+
+        // A partial line keeps the machine waiting.
+        c.provide_input(0, "po")?;
+        c.tick(3)?;
+        assert_eq!(c.seq.statuses[&0], AwaitingInput);
+
+        c.provide_input(0, "om\n")?;
+        c.tick(3)?;
+        assert_eq!(c.seq.statuses[&0], AwaitingInput);
+
+        c.provide_input(0, "!")?;
+        c.tick(3)?;
+        assert_eq!(c.seq.statuses[&0], Halted);
+        assert_eq!(c.seq.get(0).unwrap().mem.read_stack(2), [4, '!' as u16]);
+
+        Ok(())
+    }
+}
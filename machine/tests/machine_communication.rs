@@ -143,7 +143,7 @@ mod machine_communication_tests {
 
         c.load_program(0, "receive")?;
         c.load_program(1, "receive")?;
-        assert_eq!(c.run(), Err(MachineError { cause: MessageNeverReceived { id: 1 } }));
+        assert_eq!(c.run(), Err(MachineError { cause: MessageNeverReceived { id: 0 } }));
 
         Ok(())
     }
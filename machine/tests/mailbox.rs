@@ -0,0 +1,98 @@
+#[cfg(test)]
+mod mailbox_tests {
+    use machine::blocks::BlockData::{Memory, Plot};
+    use machine::canvas::{Canvas, CanvasError, MailboxOrder};
+    use machine::canvas::wire::port;
+    use machine::Action;
+
+    type Errorable = Result<(), CanvasError>;
+
+    /// Machine 0 sends a burst of three notes in a single tick to machine 1.
+    fn burst(order: MailboxOrder) -> Result<Vec<u16>, CanvasError> {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.add_machine()?;
+        c.connect(port(0, 0), port(1, 0))?;
+        c.set_mailbox_order(1, order)?;
+
+        c.load_program(0, "
+            push 60
+            send 0 1
+            push 64
+            send 0 1
+            push 67
+            send 0 1
+        ")?;
+
+        c.load_program(1, "
+            sleep_tick 3
+            receive
+            receive
+            receive
+        ")?;
+
+        c.machine_cycle_per_tick = 10;
+        c.run()?;
+
+        Ok(c.seq.get(1).unwrap().mem.read_stack(3))
+    }
+
+    #[test]
+    fn test_machine_receives_in_order() -> Errorable {
+        assert_eq!(burst(MailboxOrder::Fifo)?, [60, 64, 67]);
+        assert_eq!(burst(MailboxOrder::Lifo)?, [67, 64, 60]);
+        assert_eq!(burst(MailboxOrder::Priority)?, [60, 64, 67]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fifo_is_the_default() -> Errorable {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        assert_eq!(c.seq.get(0).unwrap().inbox.order, MailboxOrder::Fifo);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_receives_in_order() -> Errorable {
+        let mut c = Canvas::new();
+        let plot = c.add_block(Plot { values: vec![], size: 10 })?;
+
+        for v in [1, 2, 3] {
+            c.send_message_to_block(plot, Action::Data { body: vec![v] })?;
+        }
+
+        c.tick(1)?;
+
+        let Plot { values, .. } = &c.get_block(plot)?.data else { panic!("block should be a plot") };
+        assert_eq!(values, &[1, 2, 3]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_block_priority() -> Errorable {
+        let mut c = Canvas::new();
+        let fifo = c.add_block(Memory { values: vec![], auto_reset: false })?;
+        let priority = c.add_block(Memory { values: vec![], auto_reset: false })?;
+        c.set_mailbox_order(priority, MailboxOrder::Priority)?;
+
+        // The reset arrives after the data, but it is handled first by priority.
+        for id in [fifo, priority] {
+            c.send_message_to_block(id, Action::Data { body: vec![5] })?;
+            c.send_message_to_block(id, Action::Reset)?;
+        }
+
+        c.tick(1)?;
+
+        let Memory { values, .. } = &c.get_block(fifo)?.data else { panic!("block should be a memory") };
+        assert!(values.is_empty());
+
+        let Memory { values, .. } = &c.get_block(priority)?.data else { panic!("block should be a memory") };
+        assert_eq!(values, &[5]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod memory_bank_tests {
+    use machine::blocks::BlockData::{Memory, Pixel};
+    use machine::blocks::pixel::PixelMode;
+    use machine::canvas::{Canvas, CanvasError};
+    use machine::canvas::wire::port;
+    use machine::{Execute, Machine, RuntimeError};
+
+    type Errorable = Result<(), CanvasError>;
+
+    #[test]
+    fn test_read_large_memory_block() -> Errorable {
+        let values: Vec<u16> = (0..0x1000).collect();
+
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.add_block(Memory { values, auto_reset: false })?;
+        c.connect(port(0, 0), port(1, 0))?;
+
+        // Bank 5 of port 0 starts at 0xA00 in the memory block.
+        c.load_program(0, r"
+            load 0x2001
+            push 5
+            select_bank 0
+            load 0x2001
+            load 0x21FF
+        ")?;
+
+        c.run()?;
+
+        assert_eq!(c.seq.get(0).unwrap().mem.read_stack(3), [0x1, 0xA01, 0xBFF]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_large_pixel_block() -> Errorable {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.add_block(Pixel { pixels: vec![], mode: PixelMode::Replace })?;
+        c.connect(port(0, 0), port(1, 0))?;
+
+        c.load_program(0, r"
+            push 2
+            select_bank 0
+            push 7
+            store 0x2003
+        ")?;
+
+        c.run()?;
+
+        let Pixel { pixels, .. } = &c.get_block(1)?.data else { panic!("block should be a pixel block") };
+        assert_eq!(pixels.get(0x403), Some(&7));
+        Ok(())
+    }
+
+    #[test]
+    fn test_banks_are_per_port() -> Errorable {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.add_block(Memory { values: vec![1; 0x400], auto_reset: false })?;
+        c.add_block(Memory { values: vec![2; 0x400], auto_reset: false })?;
+        c.connect(port(0, 0), port(1, 0))?;
+        c.connect(port(0, 1), port(2, 0))?;
+
+        // Only port 1 switches bank, so port 0 still reads its first bank.
+        c.load_program(0, r"
+            push 1
+            select_bank 1
+            load 0x2000
+            load 0x2200
+        ")?;
+
+        c.run()?;
+
+        assert_eq!(c.seq.get(0).unwrap().mem.read_stack(2), [1, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_bank() {
+        let mut m: Machine = "push 0x80\nselect_bank 0".try_into().expect("program should parse");
+        assert_eq!(m.run(), Err(RuntimeError::InvalidBank { port: 0, bank: 0x80 }));
+    }
+}
@@ -0,0 +1,103 @@
+#[cfg(test)]
+mod memory_layout_tests {
+    use machine::{Execute, Machine, ParseError, Parser, RuntimeError, Sequencer};
+    use machine::canvas::{Canvas, CanvasError};
+    use machine::mem::{LayoutPreset, MemoryLayout};
+    use machine::sequencer::SequencerError;
+
+    /// Recurses as deep as the value on the stack.
+    fn recurse(depth: u16) -> String {
+        format!("
+            push {depth}
+            call countdown
+            halt
+
+            countdown:
+                dup
+                jump_zero done
+                dec
+                call countdown
+
+            done:
+                return
+        ")
+    }
+
+    fn machine_with(layout: MemoryLayout, source: &str) -> Machine {
+        let mut parser = Parser::with_layout(source, layout);
+        parser.parse().expect("program should parse");
+
+        let mut m = Machine::with_layout(layout);
+        m.mem.load_code(parser.ops);
+        m.mem.load_symbols(parser.symbols);
+        m
+    }
+
+    #[test]
+    fn test_deep_recursion() {
+        let source = recurse(1000);
+
+        let mut m = machine_with(MemoryLayout::default(), &source);
+        assert_eq!(m.run(), Err(RuntimeError::CallStackExceeded));
+
+        let mut m = machine_with(LayoutPreset::DeepRecursion.into(), &source);
+        m.run().expect("deep recursion should fit in the call stack");
+        assert_eq!(m.stack().peek(), 0);
+    }
+
+    #[test]
+    fn test_tiny_layout() {
+        let layout = MemoryLayout::tiny();
+        let mut m = machine_with(layout, ".string msg \"hi\"\nload_string msg\npush 5");
+
+        assert_eq!(m.mem.buffer.len(), 0x1000);
+        assert_eq!(m.mem.read_data(2), [104, 105]);
+
+        m.run().expect("program should run");
+        assert_eq!(m.mem.read_stack(3), [104, 105, 5]);
+        assert_eq!(m.reg.get(machine::Register::SP), layout.stack_start() + 2);
+    }
+
+    #[test]
+    fn test_program_must_fit_in_layout() {
+        let layout = MemoryLayout { code_size: 4, ..MemoryLayout::tiny() };
+        let mut parser = Parser::with_layout("push 1\npush 2\npush 3", layout);
+
+        assert_eq!(parser.parse(), Err(ParseError::CodeSegmentOverflow { size: 6, limit: 4 }));
+    }
+
+    #[test]
+    fn test_string_past_end_of_memory() {
+        // The data segment starts 16 words before the end of the address space.
+        let layout = MemoryLayout { code_size: 0xFFEF, ..MemoryLayout::default() };
+        let source = ".string a \"0123456789abcdefghij\"\n.string b \"x\"\nload_string b";
+        let mut parser = Parser::with_layout(source, layout);
+
+        let overflow = ParseError::DataSegmentOverflow { size: 21, limit: 16 };
+        assert_eq!(parser.parse(), Err(ParseError::InvalidArgument { errors: vec![overflow] }));
+    }
+
+    #[test]
+    fn test_sequencer_layout() {
+        let mut seq = Sequencer::new();
+        seq.add_with_layout(0, MemoryLayout::tiny()).expect("tiny layout should be valid");
+        seq.load(0, "push 1").expect("program should load");
+
+        let m = seq.get(0).expect("machine should exist");
+        assert_eq!(m.mem.layout, MemoryLayout::tiny());
+
+        let layout = MemoryLayout { memory_size: 0x100, ..MemoryLayout::default() };
+        assert_eq!(seq.add_with_layout(1, layout), Err(SequencerError::InvalidMemoryLayout { id: 1 }));
+        assert!(seq.get(1).is_none());
+    }
+
+    #[test]
+    fn test_canvas_layout() -> Result<(), CanvasError> {
+        let mut c = Canvas::new();
+        let id = c.add_machine_with_layout(LayoutPreset::DeepRecursion.into())?;
+        c.load_program(id, &recurse(1000))?;
+        c.run()?;
+
+        Ok(())
+    }
+}
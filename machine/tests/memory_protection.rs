@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod memory_protection_tests {
+    use machine::{Execute, Machine, Op, CALL_STACK_START, DATA_START, MEMORY_SIZE, STACK_START};
+    use machine::mem::Access::{Read, Write};
+    use machine::RuntimeError::{PcOutOfBounds, SegmentationFault};
+
+    #[test]
+    fn test_code_is_read_only() {
+        let mut m: Machine = vec![Op::Push(0xFF), Op::Store(0), Op::Push(1)].into();
+        assert_eq!(m.run(), Err(SegmentationFault { addr: 0, access: Write }));
+        assert_eq!(m.mem.get(0), Op::Push(0).opcode());
+    }
+
+    #[test]
+    fn test_self_modifying_code() {
+        // Replace `push 1` with `push 2`.
+        let mut m: Machine = vec![Op::Push(2), Op::Store(5), Op::Push(1)].into();
+        m.mem.self_modifying = true;
+        m.run().expect("self-modifying program should run");
+
+        assert_eq!(m.mem.read_stack(1), [2]);
+    }
+
+    #[test]
+    fn test_protected_segments() {
+        let mut m: Machine = vec![Op::Push(1), Op::Store(CALL_STACK_START)].into();
+        assert_eq!(m.run(), Err(SegmentationFault { addr: CALL_STACK_START, access: Write }));
+
+        // The stack can only be written by the stack instructions.
+        let mut m: Machine = vec![Op::Push(1), Op::Store(STACK_START)].into();
+        assert_eq!(m.run(), Err(SegmentationFault { addr: STACK_START, access: Write }));
+
+        let mut m: Machine = vec![Op::Load(MEMORY_SIZE)].into();
+        assert_eq!(m.run(), Err(SegmentationFault { addr: MEMORY_SIZE, access: Read }));
+
+        let mut m: Machine = vec![Op::Push(MEMORY_SIZE - 1), Op::Read(2)].into();
+        assert_eq!(m.run(), Err(SegmentationFault { addr: MEMORY_SIZE, access: Read }));
+
+        // The data segment is readable and writable.
+        let mut m: Machine = vec![Op::Push(5), Op::Store(DATA_START), Op::Load(DATA_START)].into();
+        m.run().expect("data segment should be writable");
+        assert_eq!(m.mem.read_stack(1), [5]);
+    }
+
+    #[test]
+    fn test_pc_out_of_bounds() {
+        let mut m: Machine = vec![Op::Jump(DATA_START + 1)].into();
+        m.mem.set(DATA_START + 1, Op::Push(0).opcode());
+
+        assert_eq!(m.run(), Err(PcOutOfBounds { pc: DATA_START + 1 }));
+    }
+}
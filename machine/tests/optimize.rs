@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod optimize_tests {
+    use machine::{Event, Execute, Machine, Op, Parser};
+
+    fn parse(src: &str, optimize: bool) -> Parser {
+        let mut parser = Parser::new(src);
+        parser.optimize = optimize;
+        parser.parse().expect("cannot parse the program");
+        parser
+    }
+
+    fn optimized_ops(src: &str) -> Vec<Op> {
+        parse(src, true).ops
+    }
+
+    /// Run the program, and return its stack and printed text.
+    fn run(parser: Parser) -> (Vec<u16>, Vec<String>) {
+        let mut m: Machine = parser.into();
+        m.run().expect("cannot run the program");
+
+        let prints = m.events.iter().filter_map(|event| match event {
+            Event::Print { text } => Some(text.clone()),
+            _ => None,
+        }).collect();
+
+        (m.mem.read_stack(10), prints)
+    }
+
+    /// The optimized program must compute the same results, with fewer operations.
+    fn assert_equivalent(src: &str) {
+        let (plain, optimized) = (parse(src, false), parse(src, true));
+        assert!(optimized.ops.len() <= plain.ops.len());
+
+        assert_eq!(run(optimized), run(plain), "optimized program differs for:\n{src}");
+    }
+
+    #[test]
+    fn test_fold_constants() {
+        assert_eq!(optimized_ops("push 2\npush 3\nadd"), [Op::Push(5)]);
+        assert_eq!(optimized_ops("push 2\npush 3\nadd\npush 4\nmul\ninc"), [Op::Push(21)]);
+        assert_eq!(optimized_ops("push 5\npush 3\nless_than"), [Op::Push(0)]);
+    }
+
+    #[test]
+    fn test_keep_failing_operations() {
+        assert_eq!(optimized_ops("push 1\npush 0\ndiv"), [Op::Push(1), Op::Push(0), Op::Div]);
+        assert_eq!(optimized_ops("push 0\npush 1\nsub"), [Op::Push(0), Op::Push(1), Op::Sub]);
+    }
+
+    #[test]
+    fn test_remove_pairs() {
+        assert_eq!(optimized_ops("push 1\npush 2\npop"), [Op::Push(1)]);
+        assert_eq!(optimized_ops("push 1\ndup\npop"), [Op::Push(1)]);
+    }
+
+    #[test]
+    fn test_remove_jump_to_next() {
+        assert_eq!(optimized_ops("push 1\njump next\nnext:\npush 2"), [Op::Push(1), Op::Push(2)]);
+    }
+
+    #[test]
+    fn test_remove_dead_code() {
+        assert_eq!(optimized_ops("push 1\nhalt\npush 2\npush 3\nend:\npush 4"), [Op::Push(1), Op::Halt, Op::Push(4)]);
+    }
+
+    #[test]
+    fn test_relocate_labels() {
+        let src = r"
+            push 2
+            push 3
+            add
+            jump start
+
+        double:
+            push 1
+            pop
+            dup
+            add
+            return
+
+        start:
+            call double
+            halt
+        ";
+
+        let parser = parse(src, true);
+
+        assert_eq!(parser.ops, [
+            Op::Push(5),
+            Op::Jump(7),
+            Op::Dup,
+            Op::Add,
+            Op::Return,
+            Op::Call(4),
+            Op::Halt,
+        ]);
+
+        assert_eq!(parser.symbols.offsets["double"], 4);
+        assert_eq!(parser.symbols.offsets["start"], 7);
+        assert_equivalent(src);
+    }
+
+    #[test]
+    fn test_keep_jump_targets() {
+        let src = r"
+            push 10
+        loop:
+            push 1
+            sub
+            dup
+            jump_not_zero loop
+            push 7
+        ";
+
+        assert_eq!(optimized_ops(src), parse(src, false).ops);
+        assert_equivalent(src);
+    }
+
+    #[test]
+    fn test_relocate_error_handler() {
+        let src = r"
+            .on_error handler
+            push 0
+            pop
+            push 1
+            push 0
+            div
+            halt
+
+        handler:
+            push 99
+            halt
+        ";
+
+        let parser = parse(src, true);
+        assert_eq!(parser.error_handler, Some(6));
+        assert_equivalent(src);
+    }
+
+    #[test]
+    fn test_differential() {
+        let programs = [
+            include_str!("asm/call-stack-1.asm"),
+            include_str!("asm/hello-world.asm"),
+            include_str!("asm/assertions.asm"),
+            "push 6\npush 7\nmul\npush 2\npush 1\nsub\nsub\nprint_int",
+            "push 3\ndup\npop\npush 4\nswap\npush 1\npush 2\nleft_shift\nrotate",
+            "push 1\njump a\npush 5\na:\njump b\nb:\npush 2\npush 3\ngreater_than_or_equal\njump_zero c\npush 9\nc:\npush 8",
+            "push 0xFFFF\npush 0xFFFF\nand\nnot\npush 4\nright_shift\nhalt\npush 1",
+        ];
+
+        for src in programs {
+            assert_equivalent(src);
+        }
+    }
+}
@@ -0,0 +1,149 @@
+#[cfg(test)]
+mod parallel_tests {
+    use machine::canvas::{Canvas, CanvasError};
+    use machine::canvas::wire::port;
+    use machine::HostRegistry;
+    use machine::sequencer::Schedule;
+    use machine::status::MachineStatus::Errored;
+
+    type Errorable = Result<(), CanvasError>;
+
+    /// A cell of the rule 90 automaton. Its next state is the XOR of its neighbours.
+    fn cell(state: u16) -> String {
+        format!("
+            push {state}
+
+            loop:
+                dup
+                send 0 1
+                dup
+                send 1 1
+                receive
+                receive
+                xor
+                nip
+                jump loop
+        ")
+    }
+
+    /// A ring of cells, where each cell talks to both of its neighbours.
+    fn automaton(size: u16) -> Result<Canvas, CanvasError> {
+        let mut c = Canvas::new();
+
+        for id in 0..size {
+            c.add_machine()?;
+            c.load_program(id, &cell(u16::from(id == size / 2)))?;
+        }
+
+        for id in 0..size {
+            c.connect(port(id, 0), port((id + 1) % size, 0))?;
+            c.connect(port(id, 1), port((id + size - 1) % size, 0))?;
+        }
+
+        c.set_seed(7);
+        c.seq.ready();
+        Ok(c)
+    }
+
+    /// Step the same canvas on a single thread and on many threads.
+    fn run_both(mut c: Canvas, ticks: u16) -> (Canvas, Canvas) {
+        let mut serial = c.clone();
+        c.seq.threads = 8;
+
+        let serial_result = serial.tick(ticks);
+        let parallel_result = c.tick(ticks);
+        assert_eq!(serial_result, parallel_result);
+
+        c.seq.threads = 1;
+        (serial, c)
+    }
+
+    fn states(c: &mut Canvas, size: u16) -> Vec<u16> {
+        (0..size).map(|id| c.seq.get_mut(id).unwrap().stack().peek()).collect()
+    }
+
+    #[test]
+    fn test_cellular_automaton() -> Errorable {
+        let (mut serial, mut parallel) = run_both(automaton(100)?, 200);
+
+        assert_eq!(serial.seq, parallel.seq);
+        assert_eq!(states(&mut serial, 100), states(&mut parallel, 100));
+
+        // The pattern spreads from the middle cell.
+        assert!(states(&mut serial, 100).iter().filter(|&&s| s == 1).count() > 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_schedules_and_failures() -> Errorable {
+        let mut c = automaton(20)?;
+
+        // Machines with their own clock speed, a random number generator and a failure.
+        let fast = c.add_machine()?;
+        let slow = c.add_machine()?;
+        let failing = c.add_machine()?;
+
+        c.load_program(fast, "loop:\n rand\n pop\n jump loop")?;
+        c.load_program(slow, "push 0\nloop:\n inc\n sleep_tick 2\n jump loop")?;
+        c.load_program(failing, "push 1\npush 0\ndiv")?;
+
+        c.set_machine_schedule(fast, Schedule::new(Some(50), 1, 5))?;
+        c.set_machine_schedule(slow, Schedule::new(Some(1), 3, -1))?;
+        c.seq.ready();
+
+        let (serial, parallel) = run_both(c, 60);
+
+        assert_eq!(serial.seq, parallel.seq);
+        assert_eq!(serial.seq.schedules, parallel.seq.schedules);
+        assert_eq!(parallel.seq.statuses[&failing], Errored);
+        Ok(())
+    }
+
+    /// Host function that pushes how many times it was called.
+    fn counter_host() -> HostRegistry {
+        let mut host = HostRegistry::new();
+        let mut calls = 0;
+
+        host.register(0, move |stack| {
+            calls += 1;
+            stack.push(calls)
+        });
+
+        host
+    }
+
+    #[test]
+    fn test_stateful_host() -> Errorable {
+        let mut c = automaton(20)?;
+
+        // Machines that call the host, and a machine that only writes a syscall at runtime.
+        let callers: Vec<u16> = (0..6).map(|_| c.add_machine()).collect::<Result<_, _>>()?;
+
+        for (i, id) in callers.iter().enumerate() {
+            c.load_program(*id, "loop:\n syscall 0\n jump loop")?;
+            c.set_machine_schedule(*id, Schedule::new(Some(1 + i as u16), 1, i as i16 % 3))?;
+        }
+
+        let writer = c.add_machine()?;
+        // Replace `push 0` with `syscall 0`, then call it on every loop.
+        c.load_program(writer, "push 0x2E\nstore 4\nloop:\n push 0\n jump loop")?;
+        c.seq.get_mut(writer).unwrap().mem.self_modifying = true;
+        c.seq.ready();
+
+        let mut serial = c.clone();
+        serial.set_host(counter_host());
+        c.set_host(counter_host());
+        c.seq.threads = 8;
+
+        assert_eq!(serial.tick(40), c.tick(40));
+
+        // Hosts are compared by identity, so compare what they pushed onto the stacks instead.
+        let stacks = |c: &Canvas| c.seq.machines.iter().map(|m| m.mem.read_stack(20)).collect::<Vec<_>>();
+        assert_eq!(stacks(&serial), stacks(&c));
+        assert_eq!(serial.seq.statuses, c.seq.statuses);
+
+        let writer_stack = c.seq.get(writer).unwrap().mem.read_stack(1);
+        assert!(writer_stack[0] > 0, "the rewritten program should call the host");
+        Ok(())
+    }
+}
@@ -88,4 +88,19 @@ mod parser_tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_large_program_with_comments() -> Errorable {
+        // Every line carries a long comment with multi-byte characters.
+        let comment = "; ünïcödé ✨ ".repeat(20);
+        let src: String = (0..2000).map(|i| format!("push {}  {comment}\n", i % 100)).collect();
+
+        let mut p = Parser::new(&src);
+        p.parse()?;
+
+        assert_eq!(p.ops.len(), 2000);
+        assert_eq!(p.ops[1999], Op::Push(99));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod print_tests {
+    use machine::{Event, Execute, Machine, RuntimeError};
+
+    fn printed(src: &str) -> Result<Vec<String>, RuntimeError> {
+        let mut m: Machine = src.try_into().expect("cannot parse the program");
+        m.run()?;
+
+        Ok(m.events.iter().filter_map(|e| match e {
+            Event::Print { text } => Some(text.clone()),
+            _ => None,
+        }).collect())
+    }
+
+    #[test]
+    fn test_print_numbers() -> Result<(), RuntimeError> {
+        let output = printed("
+            push 1234
+            print_int
+            push 0xBEEF
+            print_hex
+            push 65
+            print_char
+        ")?;
+
+        assert_eq!(output, ["1234", "beef", "A"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_print_fmt() -> Result<(), RuntimeError> {
+        let output = printed(r#"
+            .string template "%s: %d + %d = %x (%c) 100%%"
+            .string name "sum"
+
+            push name
+            push 7
+            push 9
+            push 16
+            push 33
+            print_fmt template
+        "#)?;
+
+        assert_eq!(output, ["sum: 7 + 9 = 10 (!) 100%"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_print_fmt_consumes_values() {
+        let mut m: Machine = ".string t \"%d\"\npush 5\npush 6\nprint_fmt t".try_into().expect("cannot parse the program");
+        m.run().expect("cannot run the program");
+
+        // Only the top value is printed and popped.
+        assert_eq!(m.stack().len(), 1);
+        assert_eq!(m.stack().peek(), 5);
+    }
+
+    #[test]
+    fn test_print_fmt_missing_values() {
+        let result = printed(".string t \"%d and %d\"\npush 1\nprint_fmt t");
+        assert_eq!(result, Err(RuntimeError::NotEnoughValues { min: 2, len: 1 }));
+    }
+}
@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod random_tests {
+    use machine::audio::waveform::Waveform;
+    use machine::blocks::BlockData::{Clock, Osc, Plot};
+    use machine::canvas::{Canvas, CanvasError};
+    use machine::canvas::wire::port;
+
+    type Errorable = Result<(), CanvasError>;
+
+    const SOURCE: &str = r"
+        rand
+        rand
+        push 1
+        push 7
+        rand_range
+    ";
+
+    fn run_random_program(seed: Option<u32>) -> Result<Vec<u16>, CanvasError> {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+
+        if let Some(seed) = seed {
+            c.set_seed(seed);
+        }
+
+        c.load_program(0, SOURCE)?;
+        c.run()?;
+
+        Ok(c.seq.get(0).unwrap().mem.read_stack(3))
+    }
+
+    #[test]
+    fn test_seeded_rand() -> Errorable {
+        let values = run_random_program(None)?;
+        assert_ne!(values[0], values[1]);
+        assert!((1..7).contains(&values[2]));
+
+        // The same seed replays the same values.
+        assert_eq!(run_random_program(None)?, values);
+        assert_eq!(run_random_program(Some(1234))?, run_random_program(Some(1234))?);
+        assert_ne!(run_random_program(Some(1234))?, values);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rand_replays_after_ready() -> Errorable {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.add_machine()?;
+        c.load_program(0, SOURCE)?;
+        c.load_program(1, SOURCE)?;
+
+        c.run()?;
+        let first = c.seq.get(0).unwrap().mem.read_stack(3);
+
+        // Each machine gets its own random sequence.
+        assert_ne!(c.seq.get(1).unwrap().mem.read_stack(3), first);
+
+        c.run()?;
+        assert_eq!(c.seq.get(0).unwrap().mem.read_stack(3), first);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_noise_oscillator() -> Errorable {
+        let mut c = Canvas::new();
+        c.add_block(Clock { time: 0, freq: 1, ping: false })?;
+        c.add_block(Osc { waveform: Waveform::Noise })?;
+        c.add_block(Plot { values: vec![], size: 10 })?;
+        c.connect(port(0, 0), port(1, 0))?;
+        c.connect(port(1, 0), port(2, 0))?;
+
+        let mut replay = c.clone();
+        c.tick(12)?;
+        replay.tick(12)?;
+
+        let Plot { values, .. } = c.blocks[2].data.clone() else { panic!("not a plot block") };
+        assert_eq!(values.len(), 10);
+        assert!(values.iter().any(|v| *v != values[0]), "noise should not be constant");
+        assert!(values.iter().all(|v| *v <= 255));
+
+        assert_eq!(replay.blocks[2].data, c.blocks[2].data);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+#[cfg(test)]
+mod schedule_tests {
+    use machine::canvas::{Canvas, CanvasError};
+    use machine::sequencer::{Schedule, SequencerError};
+    use machine::HostRegistry;
+    use std::sync::{Arc, Mutex};
+
+    type Errorable = Result<(), CanvasError>;
+
+    /// Counts up forever.
+    const COUNTER: &str = "
+        push 0
+
+        loop:
+            inc
+            jump loop
+    ";
+
+    fn counter(c: &mut Canvas, id: u16) -> u16 {
+        c.seq.get_mut(id).unwrap().stack().peek()
+    }
+
+    #[test]
+    fn test_per_machine_clock_speed() -> Errorable {
+        let mut c = Canvas::new();
+        let fast = c.add_machine()?;
+        let slow = c.add_machine()?;
+        let default = c.add_machine()?;
+
+        for id in [fast, slow, default] {
+            c.load_program(id, COUNTER)?;
+        }
+
+        c.set_machine_schedule(fast, Schedule::new(Some(10), 1, 0))?;
+        c.set_machine_schedule(slow, Schedule::new(Some(1), 4, 0))?;
+        c.machine_cycle_per_tick = 2;
+
+        c.seq.ready();
+        c.tick(8)?;
+
+        // The fast machine runs 80 cycles, the slow machine 2 cycles and the default machine 16 cycles.
+        assert_eq!(c.seq.get(fast).unwrap().cycles, 80);
+        assert_eq!(c.seq.get(slow).unwrap().cycles, 2);
+        assert_eq!(c.seq.get(default).unwrap().cycles, 16);
+
+        // Every other instruction of the loop increments the counter.
+        assert_eq!(counter(&mut c, slow), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_priority_order() -> Errorable {
+        // Records the order in which the machines call the host.
+        let calls = Arc::new(Mutex::new(vec![]));
+        let mut host = HostRegistry::new();
+        let log = calls.clone();
+        host.register(1, move |s| Ok(log.lock().unwrap().push(s.pop()?)));
+
+        let mut c = Canvas::new();
+        c.set_host(host);
+
+        for id in 0..3 {
+            c.add_machine()?;
+            c.load_program(id, &format!("push {id}\nsyscall 1"))?;
+        }
+
+        c.set_machine_schedule(2, Schedule::new(None, 1, 10))?;
+        c.set_machine_schedule(0, Schedule::new(None, 1, -1))?;
+
+        c.machine_cycle_per_tick = 2;
+        c.run()?;
+
+        assert_eq!(*calls.lock().unwrap(), [2, 1, 0]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_schedule_missing_machine() {
+        let mut c = Canvas::new();
+
+        assert_eq!(
+            c.set_machine_schedule(5, Schedule::default()),
+            Err(CanvasError::MachineError { cause: SequencerError::MachineDoesNotExist { id: 5 } })
+        );
+    }
+}
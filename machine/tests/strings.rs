@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod tests {
-    use machine::{Execute, Machine, Op, RuntimeError, WithStringManager};
+    use machine::{Event, Execute, Machine, Op, RuntimeError, WithStringManager};
 
     /// Loads string manually using the Load instruction.
     /// Note that the LoadString instruction is a more convenient alternative.
@@ -24,4 +24,41 @@ mod tests {
 
         Ok(())
     }
+
+    fn run(src: &str) -> Machine {
+        let mut m: Machine = src.try_into().expect("cannot parse the program");
+        m.run().expect("cannot run the program");
+        m
+    }
+
+    #[test]
+    fn test_print_utf16() {
+        let m = run(".string s \"héllo 🎉\"\npush 0\nload_string s\nprint");
+        assert_eq!(m.events, [Event::Print { text: "héllo 🎉".into() }]);
+    }
+
+    #[test]
+    fn test_string_length() {
+        // The emoji takes two code units.
+        let m = run(".string s \"héllo 🎉\"\n.string empty \"\"\nstr_len s\nstr_len empty");
+        assert_eq!(m.mem.read_stack(2), [8, 0]);
+    }
+
+    #[test]
+    fn test_char_at() -> Result<(), RuntimeError> {
+        let m = run(".string s \"héllo\"\npush 1\nchar_at s\npush 4\nchar_at s");
+        assert_eq!(m.mem.read_stack(2), ['é' as u16, 'o' as u16]);
+
+        let mut m: Machine = ".string s \"hi\"\npush 2\nchar_at s".try_into().expect("cannot parse the program");
+        assert_eq!(m.run(), Err(RuntimeError::IndexOutOfBounds { index: 2, len: 2 }));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strings_after_emoji() {
+        // Symbols after a multi-unit string must not overlap it.
+        let m = run(".string a \"🎉🎉\"\n.string b \"ok\"\nload_string b");
+        assert_eq!(m.mem.read_stack(2), ['o' as u16, 'k' as u16]);
+    }
 }
@@ -0,0 +1,64 @@
+#[cfg(test)]
+mod syscall_tests {
+    use machine::{Execute, Host, HostRegistry, Machine, Op, RuntimeError};
+    use machine::canvas::{Canvas, CanvasError};
+    use machine::RuntimeError::{MissingHostFunction, StackUnderflow};
+
+    fn test_host() -> HostRegistry {
+        let mut host = HostRegistry::new();
+
+        // Doubles the value at the top of the stack.
+        host.register(1, |s| s.apply(|v| Ok(v * 2)));
+
+        // Pushes two constant values.
+        host.register(2, |s| {
+            s.push(0xAA)?;
+            s.push(0xBB)
+        });
+
+        host
+    }
+
+    #[test]
+    fn test_syscall() -> Result<(), RuntimeError> {
+        let mut m: Machine = vec![Op::Push(21), Op::Syscall(1), Op::Syscall(2)].into();
+        m.host = Host::new(test_host());
+        m.run()?;
+
+        assert_eq!(m.mem.read_stack(3), [42, 0xAA, 0xBB]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_syscall_errors() {
+        let mut m: Machine = vec![Op::Syscall(1)].into();
+        assert_eq!(m.run(), Err(MissingHostFunction { id: 1 }));
+
+        let mut m: Machine = vec![Op::Syscall(3)].into();
+        m.host = Host::new(test_host());
+        assert_eq!(m.run(), Err(MissingHostFunction { id: 3 }));
+
+        // Errors from the host function are raised by the machine.
+        let mut m: Machine = vec![Op::Syscall(1)].into();
+        m.host = Host::new(test_host());
+        assert!(matches!(m.run(), Err(StackUnderflow { .. })));
+    }
+
+    #[test]
+    fn test_canvas_host() -> Result<(), CanvasError> {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.set_host(test_host());
+        c.add_machine()?;
+
+        c.load_program(0, "push 5\nsyscall 1")?;
+        c.load_program(1, "syscall 2")?;
+        c.run()?;
+
+        assert_eq!(c.seq.get(0).unwrap().mem.read_stack(1), [10]);
+        assert_eq!(c.seq.get(1).unwrap().mem.read_stack(2), [0xAA, 0xBB]);
+
+        Ok(())
+    }
+}
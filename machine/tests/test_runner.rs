@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod test_runner_tests {
+    use machine::{load_test_file, Execute, Machine, RuntimeError};
+    use machine::cli::run_tests;
+    use machine::cli::test_runner::{test_source, Expectations, MAX_TEST_CYCLES};
+
+    #[test]
+    fn test_assembly_tests_pass() {
+        for file in ["assertions.asm", "call-stack-1.asm", "hello-world.asm"] {
+            assert_eq!(test_source(&load_test_file(file)), Ok(()), "{file} should pass");
+        }
+    }
+
+    #[test]
+    fn test_assertion_failed() {
+        let mut m: Machine = "push 1\npush 2\nassert_eq".try_into().expect("cannot parse");
+        assert_eq!(m.run(), Err(RuntimeError::AssertionFailed { pc: 4, expected: 2, actual: 1 }));
+
+        let mut m: Machine = "push 0\nassert".try_into().expect("cannot parse");
+        assert_eq!(m.run(), Err(RuntimeError::AssertionFailed { pc: 2, expected: 1, actual: 0 }));
+
+        let reason = test_source("push 3\npush 4\nassert_eq").unwrap_err();
+        assert_eq!(reason, "assertion at 4 failed. expected 4, got 3");
+    }
+
+    #[test]
+    fn test_assertion_error_handler() {
+        let mut m: Machine = "
+            .on_error handler
+            push 0
+            assert
+            halt
+
+            handler:
+                push 99
+        ".try_into().expect("cannot parse");
+
+        m.run().expect("the handler should catch the assertion");
+        assert_eq!(m.mem.read_stack(2), [18, 99]);
+    }
+
+    #[test]
+    fn test_expectations() {
+        let source = "push 1 ; expect-stack: 9\n; expect-stack: 1 0x10\n; expect-output: hi there \n";
+        let expect = Expectations::from_source(source).expect("cannot read expectations");
+
+        // Trailing comments after an instruction are not expectations.
+        assert_eq!(expect.stack, Some(vec![1, 16]));
+        assert_eq!(expect.output, Some(vec!["hi there".into()]));
+
+        assert!(Expectations::from_source("; expect-stack: nope").is_err());
+
+        let expect = Expectations::from_source("; test-skip: needs a canvas\npush 1").expect("cannot read expectations");
+        assert_eq!(expect.skip, Some("needs a canvas".into()));
+    }
+
+    #[test]
+    fn test_sample_directory() {
+        let dir = env!("CARGO_MANIFEST_DIR").to_owned() + "/tests/asm";
+        assert!(run_tests(&dir).is_ok(), "the sample programs should pass or be skipped");
+    }
+
+    #[test]
+    fn test_endless_loop_times_out() {
+        let reason = test_source("loop:\njump loop").unwrap_err();
+        assert_eq!(reason, format!("timed out after {MAX_TEST_CYCLES} cycles"));
+    }
+
+    #[test]
+    fn test_host_functions() {
+        assert_eq!(test_source("syscall 0\nsyscall 1\npop\npop\npop"), Ok(()));
+    }
+
+    #[test]
+    fn test_expectations_mismatch() {
+        let reason = test_source("push 1\npush 2\n; expect-stack: 1").unwrap_err();
+        assert_eq!(reason, "expected stack [1], got [1, 2]");
+
+        let reason = test_source("push 7\nprint_int\n; expect-output: 8").unwrap_err();
+        assert_eq!(reason, "expected output [\"8\"], got [\"7\"]");
+    }
+}
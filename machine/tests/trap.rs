@@ -0,0 +1,116 @@
+#[cfg(test)]
+mod trap_tests {
+    use machine::{Execute, Machine, ParseError, Parser, RuntimeError};
+    use machine::canvas::{Canvas, CanvasError};
+    use machine::canvas::CanvasError::MachineError;
+    use machine::ExecutionFailed;
+    use machine::ParseError::{DuplicateErrorHandler, UndefinedSymbols};
+    use machine::RuntimeError::{CannotDivideByZero, IntegerOverflow};
+    use machine::status::MachineStatus::{Errored, Halted};
+
+    #[test]
+    fn test_error_handler() -> Result<(), RuntimeError> {
+        let mut m: Machine = (*r"
+            .on_error handler
+
+            push 1
+            push 0
+            div
+            push 0xAA
+            halt
+
+            handler:
+                push 0xEE
+                return
+        ").try_into().expect("cannot parse the program");
+
+        m.run()?;
+
+        // The handler receives the error code, then resumes after the faulty instruction.
+        assert_eq!(m.mem.read_stack(3), [CannotDivideByZero.code(), 0xEE, 0xAA]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_arithmetic_faults_are_trapped() {
+        for (op, error) in [("mod", CannotDivideByZero), ("left_shift", IntegerOverflow), ("right_shift", IntegerOverflow)] {
+            let mut m: Machine = (*format!(r"
+                .on_error handler
+                push 1
+                push {}
+                {op}
+                halt
+
+                handler:
+                    return
+            ", if op == "mod" { 0 } else { 16 })).try_into().expect("cannot parse the program");
+
+            m.run().unwrap_or_else(|e| panic!("the handler should catch the fault of {op}: {e:?}"));
+            assert_eq!(m.mem.read_stack(1), [error.code()], "{op} should raise {error:?}");
+        }
+    }
+
+    #[test]
+    fn test_nested_fault() {
+        let mut m: Machine = (*r"
+            .on_error handler
+            pop
+
+            handler:
+                pop
+                pop
+        ").try_into().expect("cannot parse the program");
+
+        // Faults inside the handler eventually exhaust the call stack.
+        assert!(matches!(m.run(), Err(RuntimeError::StackUnderflow { .. })));
+    }
+
+    #[test]
+    fn test_pc_out_of_bounds_is_not_trapped() {
+        for target in ["0xFFFF", "0x4000"] {
+            let mut m: Machine = (*format!(r"
+                .on_error handler
+                jump {target}
+
+                handler:
+                    return
+            ")).try_into().expect("cannot parse the program");
+
+            // Returning from the handler would resume outside of the code segment again.
+            assert!(matches!(m.run(), Err(RuntimeError::PcOutOfBounds { .. })));
+        }
+    }
+
+    #[test]
+    fn test_error_handler_parse_errors() {
+        let p: Result<Parser, ParseError> = (*".on_error missing\npush 1").try_into();
+        assert_eq!(p.err(), Some(UndefinedSymbols));
+
+        let p: Result<Parser, ParseError> = (*".value foo 1\n.on_error foo\npush 1").try_into();
+        assert_eq!(p.err(), Some(UndefinedSymbols));
+
+        let p: Result<Parser, ParseError> = (*".on_error a\n.on_error a\na:\npush 1").try_into();
+        assert_eq!(p.err(), Some(DuplicateErrorHandler));
+    }
+
+    #[test]
+    fn test_fault_isolation() -> Result<(), CanvasError> {
+        let mut c = Canvas::new();
+        c.add_machine()?;
+        c.add_machine()?;
+
+        c.load_program(0, "push 1\npush 0\ndiv")?;
+        c.load_program(1, "push 1\npush 2\npush 3\npush 4\npush 5")?;
+
+        let result = c.run();
+        assert_eq!(result, Err(MachineError { cause: ExecutionFailed { id: 0, error: CannotDivideByZero } }));
+
+        // The other machine keeps running until it halts.
+        assert_eq!(c.seq.statuses[&0], Errored);
+        assert_eq!(c.seq.statuses[&1], Halted);
+        assert_eq!(c.seq.get(1).unwrap().mem.read_stack(5), [1, 2, 3, 4, 5]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,186 @@
+#[cfg(test)]
+mod verify_tests {
+    use machine::{Op, Parser, StackEffect, StackWarning};
+
+    fn verify(src: &str) -> Vec<StackWarning> {
+        let parser: Parser = src.try_into().expect("cannot parse the program");
+        parser.verify()
+    }
+
+    #[test]
+    fn test_stack_effects() {
+        assert_eq!(Op::Push(1).stack_effect(), Some(StackEffect { pops: 0, pushes: 1 }));
+        assert_eq!(Op::Add.stack_effect(), Some(StackEffect { pops: 2, pushes: 1 }));
+        assert_eq!(Op::Pick(2).stack_effect(), Some(StackEffect { pops: 3, pushes: 4 }));
+        assert_eq!(Op::Send(0, 3).stack_effect(), Some(StackEffect { pops: 3, pushes: 0 }));
+        assert_eq!(Op::Read(3).stack_effect(), Some(StackEffect { pops: 1, pushes: 3 }));
+        assert_eq!(Op::Write(3).stack_effect(), None);
+        assert_eq!(Op::Receive.stack_effect(), None);
+    }
+
+    #[test]
+    fn test_balanced_programs() {
+        for src in [
+            include_str!("asm/call-stack-1.asm"),
+            include_str!("asm/hello-world.asm"),
+            include_str!("asm/assertions.asm"),
+            include_str!("asm/palindrome.asm"),
+        ] {
+            assert_eq!(verify(src), vec![]);
+        }
+    }
+
+    #[test]
+    fn test_underflow() {
+        let src = "push 1\npush 2\nadd\nmul";
+
+        assert_eq!(verify(src), vec![
+            StackWarning::StackUnderflow { line: 4, op: "mul".into(), depth: 1, pops: 2 }
+        ]);
+    }
+
+    #[test]
+    fn test_underflow_in_branch() {
+        let src = r"
+            push 0
+            jump_zero empty
+            halt
+        empty:
+            pop
+        ";
+
+        assert_eq!(verify(src), vec![
+            StackWarning::StackUnderflow { line: 6, op: "pop".into(), depth: 0, pops: 1 }
+        ]);
+    }
+
+    #[test]
+    fn test_underflow_of_subroutine_arguments() {
+        let src = r"
+            push 1
+            call sum
+            halt
+
+        sum:
+            add
+            return
+        ";
+
+        assert_eq!(verify(src), vec![
+            StackWarning::StackUnderflow { line: 3, op: "call".into(), depth: 1, pops: 2 }
+        ]);
+
+        assert_eq!(verify(&src.replace("push 1", "push 1\npush 2")), vec![]);
+    }
+
+    #[test]
+    fn test_unbalanced_loop() {
+        let src = r"
+            push 5
+        loop:
+            push 1
+            swap
+            dec
+            dup
+            jump_not_zero loop
+        ";
+
+        assert_eq!(verify(src), vec![StackWarning::UnbalancedLoop { line: 8, delta: 1 }]);
+    }
+
+    #[test]
+    fn test_balanced_loop() {
+        let src = r"
+            push 5
+        loop:
+            dec
+            dup
+            jump_not_zero loop
+            pop
+        ";
+
+        assert_eq!(verify(src), vec![]);
+    }
+
+    #[test]
+    fn test_backward_jump_to_join_is_not_a_loop() {
+        let src = r"
+            jump start
+        join:
+            halt
+        start:
+            push 0
+            jump_zero other
+            jump join
+        other:
+            push 7
+            jump join
+        ";
+
+        assert_eq!(verify(src), vec![]);
+    }
+
+    #[test]
+    fn test_underflow_on_one_branch_is_tolerated() {
+        let src = r"
+            push 0
+            jump_zero a
+            jump b
+        a:
+            push 9
+            jump b
+        b:
+            pop
+            halt
+        ";
+
+        assert_eq!(verify(src), vec![]);
+    }
+
+    #[test]
+    fn test_mismatched_return_depth() {
+        let src = r"
+            push 0
+            call f
+            halt
+
+        f:
+            jump_zero skip
+            push 1
+            return
+        skip:
+            return
+        ";
+
+        let warnings = verify(src);
+        assert_eq!(warnings.len(), 1);
+        assert!(matches!(warnings[0], StackWarning::MismatchedReturnDepth { .. }));
+    }
+
+    #[test]
+    fn test_return_without_call() {
+        assert_eq!(verify("push 1\nreturn"), vec![StackWarning::ReturnWithoutCall { line: 2 }]);
+    }
+
+    #[test]
+    fn test_dynamic_effects_are_not_followed() {
+        assert_eq!(verify("receive\nadd"), vec![]);
+    }
+
+    #[test]
+    fn test_short_stack_is_tolerated() {
+        // `swap` leaves a single value in place, and `write` stops at the bottom of the stack.
+        assert_eq!(verify("push 1\nswap\nhalt"), vec![]);
+        assert_eq!(verify("push 1\npush 0x1000\nwrite 3\nhalt"), vec![]);
+
+        assert_eq!(verify("swap"), vec![
+            StackWarning::StackUnderflow { line: 1, op: "swap".into(), depth: 0, pops: 1 }
+        ]);
+    }
+
+    #[test]
+    fn test_warning_message() {
+        let warning = StackWarning::StackUnderflow { line: 4, op: "mul".into(), depth: 1, pops: 2 };
+        assert_eq!(warning.to_string(), "line 4: 'mul' needs 2 values, but the stack only has 1");
+    }
+}